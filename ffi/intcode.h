@@ -0,0 +1,47 @@
+// The intcode interpreter, from C.
+//
+// Link against the library built by:
+//   cargo rustc --release --lib --features ffi --crate-type cdylib
+// Create a computer with intcode_new, and free it with intcode_free.
+// intcode_run runs until it halts, faults, or needs input, which intcode_push_input gives it;
+// intcode_drain_output collects what it has output.
+// Computers run in checked mode: overflow and bad instructions are faults, not crashes.
+
+#ifndef INTCODE_H
+#define INTCODE_H
+
+// Generated by cbindgen --config ffi/cbindgen.toml --output ffi/intcode.h; don't edit.
+
+#include <stdint.h>
+#include <stddef.h>
+
+typedef enum IntcodeStop {
+  INTCODE_STOP_RUNNING,
+  INTCODE_STOP_HALTED,
+  INTCODE_STOP_NEEDS_INPUT,
+  INTCODE_STOP_FAULTED,
+} IntcodeStop;
+
+typedef struct IntcodeComputer IntcodeComputer;
+
+struct IntcodeComputer *intcode_new(const int64_t *mem, size_t len);
+
+void intcode_free(struct IntcodeComputer *ic);
+
+enum IntcodeStop intcode_step(struct IntcodeComputer *ic);
+
+enum IntcodeStop intcode_run(struct IntcodeComputer *ic);
+
+void intcode_push_input(struct IntcodeComputer *ic, int64_t v);
+
+size_t intcode_output_len(const struct IntcodeComputer *ic);
+
+size_t intcode_drain_output(struct IntcodeComputer *ic, int64_t *buf, size_t cap);
+
+int64_t intcode_get(const struct IntcodeComputer *ic, size_t addr);
+
+void intcode_set(struct IntcodeComputer *ic, size_t addr, int64_t v);
+
+size_t intcode_pos(const struct IntcodeComputer *ic);
+
+#endif  /* INTCODE_H */
@@ -0,0 +1,96 @@
+// Exercises the C interface. From the top of the repository:
+//   cargo rustc --release --lib --features ffi --crate-type cdylib
+//   cc -Wall -Wextra -o target/ffi_test ffi/test.c -Ltarget/release -ladventofcode
+//   LD_LIBRARY_PATH=target/release target/ffi_test
+
+#include <assert.h>
+#include <stdio.h>
+
+#include "intcode.h"
+
+#define LEN(a) (sizeof(a) / sizeof((a)[0]))
+
+// Outputs 1 if its input is 8, else 0, then halts (day 5's example).
+static void equal_to_8(void) {
+  const int64_t mem[] = {3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8};
+  for (int64_t input = 7; input <= 8; input++) {
+    IntcodeComputer *ic = intcode_new(mem, LEN(mem));
+    assert(intcode_run(ic) == INTCODE_STOP_NEEDS_INPUT);
+    assert(intcode_pos(ic) == 0);
+    intcode_push_input(ic, input);
+    assert(intcode_run(ic) == INTCODE_STOP_HALTED);
+
+    int64_t out[4];
+    assert(intcode_output_len(ic) == 1);
+    assert(intcode_drain_output(ic, out, LEN(out)) == 1);
+    assert(out[0] == (input == 8));
+    assert(intcode_output_len(ic) == 0);
+    // Halted stays halted.
+    assert(intcode_step(ic) == INTCODE_STOP_HALTED);
+    intcode_free(ic);
+  }
+}
+
+// Outputs a copy of itself (day 9's example), drained a few at a time.
+static void quine(void) {
+  const int64_t mem[] = {109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99};
+  IntcodeComputer *ic = intcode_new(mem, LEN(mem));
+  assert(intcode_run(ic) == INTCODE_STOP_HALTED);
+
+  int64_t out[5];
+  size_t got = 0;
+  size_t n;
+  while ((n = intcode_drain_output(ic, out, LEN(out))) > 0) {
+    for (size_t i = 0; i < n; i++) {
+      assert(out[i] == mem[got + i]);
+    }
+    got += n;
+  }
+  assert(got == LEN(mem));
+  // Memory past the image reads as 0 until written.
+  assert(intcode_get(ic, 100) == 16);
+  assert(intcode_get(ic, 1000) == 0);
+  intcode_free(ic);
+}
+
+// Stepping, and patching memory before running, as day 2 does.
+static void patched(void) {
+  const int64_t mem[] = {1, 0, 0, 0, 99};
+  IntcodeComputer *ic = intcode_new(mem, LEN(mem));
+  intcode_set(ic, 1, 4);
+  intcode_set(ic, 2, 4);
+  assert(intcode_step(ic) == INTCODE_STOP_RUNNING);
+  assert(intcode_get(ic, 0) == 198);
+  assert(intcode_pos(ic) == 4);
+  assert(intcode_step(ic) == INTCODE_STOP_HALTED);
+  intcode_free(ic);
+}
+
+// Bad programs fault rather than crashing.
+static void faults(void) {
+  const int64_t bad_opcode[] = {1, 0, 0, 0, 42};
+  IntcodeComputer *ic = intcode_new(bad_opcode, LEN(bad_opcode));
+  assert(intcode_run(ic) == INTCODE_STOP_FAULTED);
+  assert(intcode_pos(ic) == 4);
+  intcode_free(ic);
+
+  const int64_t overflow[] = {1102, INT64_MAX, 2, 5, 99};
+  ic = intcode_new(overflow, LEN(overflow));
+  assert(intcode_run(ic) == INTCODE_STOP_FAULTED);
+  assert(intcode_pos(ic) == 0);
+  intcode_free(ic);
+
+  ic = intcode_new(NULL, 0);
+  assert(intcode_run(ic) == INTCODE_STOP_FAULTED);
+  intcode_free(ic);
+  intcode_free(NULL);
+}
+
+int main(void) {
+  equal_to_8();
+  quine();
+  patched();
+  faults();
+  puts("ok");
+  return 0;
+}
@@ -10,7 +10,7 @@ fn parse_orbit(s: &str) -> (&str, &str) {
     }
 }
 
-fn parse_orbits<'a>(s: &'a str) -> (Option<&str>, Option<&'a str>, Orbits<'a, 'a>) {
+fn parse_orbits<'a>(s: &'a str) -> (Option<&'a str>, Option<&'a str>, Orbits<'a, 'a>) {
     let mut you_orbit = None;
     let mut san_orbit = None;
     let mut orbit = HashMap::new();
@@ -16,7 +16,7 @@ where
 
     each_perm_gen(k - 1, v, f);
     for i in 0..(k - 1) {
-        if k % 2 == 0 {
+        if k.is_multiple_of(2) {
             v.swap(i, k - 1);
         } else {
             v.swap(0, k - 1);
@@ -31,7 +31,7 @@ fn const_input(mem: &[i64], phase: i64, input: i64) -> Vec<i64> {
     while !ic.is_halted() {
         ic.cont_in(input);
     }
-    ic.output
+    ic.output.into_inner()
 }
 
 struct Amp {
@@ -65,7 +65,7 @@ fn chain(mem: &[i64], min_phase: u8) -> i64 {
 
     let num_rounds = *lengths.iter().next().unwrap();
 
-    let mut max = std::i64::MIN;
+    let mut max = i64::MIN;
 
     each_perm(&mut phases, |perm| {
         let mut signal = 0;
@@ -64,7 +64,7 @@ fn run_precomp(mem: &[i64]) -> (HashSet<Pos>, HashSet<Pos>) {
     run(false, |w| {
         if !used_initial {
             used_initial = true;
-            Some(initial.drain(..).collect())
+            Some(std::mem::take(&mut initial))
         } else if i < num_rounds {
             let prev = std::mem::replace(&mut state[i % cycle_len], w);
             i += 1;
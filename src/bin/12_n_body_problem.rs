@@ -29,7 +29,7 @@ fn run1k(poses: &[i32]) -> Vec<(i32, i32)> {
         step(&mut poses, &mut vels);
     }
 
-    poses.into_iter().zip(vels.into_iter()).collect()
+    poses.into_iter().zip(vels).collect()
 }
 
 fn period(poses0: &[i32]) -> u64 {
@@ -68,7 +68,7 @@ fn gcd(a: u64, b: u64) -> u64 {
 
 fn main() {
     let moons = adventofcode::read_input_lines(adventofcode::numbers);
-    let len = moons.get(0).map_or(0, Vec::len);
+    let len = moons.first().map_or(0, Vec::len);
     if moons.iter().any(|moon| moon.len() != len) {
         panic!("uneven lengths {:?}", moons);
     }
@@ -23,7 +23,7 @@ fn ore_to_make<'a>(
             }
 
             let (produced, ref inputs) = recipes[thing];
-            let times = (amount_needed + produced - 1) / produced;
+            let times = amount_needed.div_ceil(produced);
             if times == 0 {
                 continue;
             }
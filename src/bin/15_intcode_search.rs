@@ -1,3 +1,5 @@
+use adventofcode::intcode::explore::{self, Order};
+use adventofcode::intcode::Computer;
 use adventofcode::search::{bfs, Result};
 use std::collections::HashMap;
 
@@ -10,39 +12,26 @@ fn adj(p: Pos) -> [Pos; 4] {
 
 fn explore(mem: &[i64]) -> HashMap<Pos, i64> {
     let mut statuses = HashMap::new();
-    let mut computers = HashMap::new();
-    computers.insert((0, 0), adventofcode::intcode::Computer::new(mem));
 
-    // Since I need to mutably borrow computers + statuses in neighbours,
-    // won't be able to use them in goal.
-    // So just explore the whole maze w/ const false goal.
-    let neigh = |pos: Pos| {
-        let adj = adj(pos);
-        let neighs = adj.iter().enumerate().filter_map(|(i, &npos)| {
-            let status = *statuses.entry(npos).or_insert_with(|| {
-                let mut ic = computers[&pos].clone();
-                ic.cont_in(i64::try_from(i).unwrap() + 1);
-                if ic.output.len() != 1 {
-                    panic!("bad output {:?}", ic.output);
-                }
-                let status = ic.output[ic.output.len() - 1];
-                ic.output.clear();
-                computers.insert(npos, ic);
-                status
-            });
-            if !(0..=2).contains(&status) {
-                panic!("bad status {} at {:?}", status, npos);
-            }
-            if status != 0 {
-                Some(npos)
-            } else {
-                None
-            }
-        });
-        neighs.collect::<Vec<_>>()
+    // Explore the whole maze, recording walls as well as where the droid can go.
+    let dirs = |_: &Pos, _: &Computer| (1..=4).map(|d| vec![d]).collect();
+    let moved = |&pos: &Pos, input: &[i64], _: &Computer, output: &[i64]| {
+        let npos = adj(pos)[usize::try_from(input[0] - 1).unwrap()];
+        let status = match output {
+            &[status] => status,
+            _ => panic!("bad output {:?}", output),
+        };
+        if !(0..=2).contains(&status) {
+            panic!("bad status {} at {:?}", status, npos);
+        }
+        statuses.insert(npos, status);
+        if status != 0 {
+            Some(npos)
+        } else {
+            None
+        }
     };
-
-    bfs((0, 0), std::usize::MAX, neigh, |_| false);
+    explore::explore(Computer::new(mem), (0, 0), Order::Breadth, dirs, moved);
 
     statuses
 }
@@ -69,7 +58,7 @@ fn main() {
     let result = search(&statuses, (0, 0), 1, 2);
     println!("{}", result.gen);
 
-    let oxygen = result.goals.get(0).unwrap().0;
-    let result = search(&statuses, oxygen, std::usize::MAX, -1);
+    let oxygen = result.goals.first().unwrap().0;
+    let result = search(&statuses, oxygen, usize::MAX, -1);
     println!("{}", result.gen);
 }
@@ -24,7 +24,7 @@ fn main() {
     let s = adventofcode::read_input_file();
 
     let input = s.trim().bytes().map(|digit| {
-        if !(b'0'..=b'9').contains(&digit) {
+        if !digit.is_ascii_digit() {
             panic!("bad digit {}", digit);
         }
         digit - b'0'
@@ -67,9 +67,7 @@ fn dust(mem: &[i64]) -> (usize, std::ops::Range<usize>) {
         functions(mem).into_iter().filter(|f| {
             mem[f.clone()].windows(4).any(|inst| {
                 let a = inst[0];
-                a < 10000
-                    && (a % 100 == 1 || a % 100 == 2)
-                    && usize::try_from(inst[3]).map_or(false, |d| d == dust)
+                a < 10000 && (a % 100 == 1 || a % 100 == 2) && usize::try_from(inst[3]) == Ok(dust)
             })
         }),
     );
@@ -178,13 +176,20 @@ fn exactly_one<T: std::fmt::Debug>(name: &str, mut it: impl Iterator<Item = T>)
 }
 
 fn main() {
-    let maybe_img = adventofcode::read_input_file();
-    let (mem, map) = if maybe_img.contains('#') {
-        (None, read_ascii_map(&maybe_img))
-    } else {
-        let mem = adventofcode::read_input_file_or_intcode();
-        let map = read_intcode_map(&mem);
-        (Some(mem), map)
+    // A map doesn't parse as intcode, so anything that does is the program.
+    let (mem, map) = match adventofcode::read_input_intcode_or_text() {
+        Ok(mem) => {
+            use adventofcode::intcode::fingerprint;
+            if let Err(Some(m)) = fingerprint::expect_day(&mem, 17) {
+                panic!(
+                    "looks like day {}'s {}, not day 17",
+                    m.family.day, m.family.name
+                );
+            }
+            let map = read_intcode_map(&mem);
+            (Some(mem), map)
+        }
+        Err(img) => (None, read_ascii_map(&img)),
     };
 
     let (_, _, align) = map;
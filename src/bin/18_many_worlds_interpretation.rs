@@ -34,7 +34,7 @@ fn key_to_key(
                 adj.iter().filter(|n| !walls.contains(n)).cloned().collect()
             }
         };
-        bfs(src, std::usize::MAX, neigh, have_new_key)
+        bfs(src, usize::MAX, neigh, have_new_key)
     };
 
     let keys_and_doors = |pos: Pos, prev: &HashMap<Pos, Pos>| {
@@ -70,7 +70,7 @@ fn key_to_key(
 fn all_pairs(keys_from: &mut [HashMap<KeyPos, Key>]) -> Vec<Vec<Key>> {
     let add_if_better = |kfi: &mut HashMap<KeyPos, Key>, ik: Key, kj: Key, j: KeyPos| {
         let new_dist = ik.dist + kj.dist;
-        if kfi.get(&j).map_or(false, |ij| ij.dist <= new_dist) {
+        if kfi.get(&j).is_some_and(|ij| ij.dist <= new_dist) {
             return;
         }
         let k = Key {
@@ -13,7 +13,7 @@ fn coeffs(mem: &[i64]) -> Vec<i64> {
         immed_to_stack
             .iter()
             .filter_map(|&(addr, v)| if addr < call { Some(v) } else { None })
-            .last()
+            .next_back()
     });
     coeffs.collect()
 }
@@ -22,9 +22,7 @@ fn calls_to_func(mem: &[i64], f: usize) -> Vec<usize> {
     let calls = mem.windows(3).enumerate().filter_map(|(i, inst)| {
         let j = inst[0];
         let arg = inst[1];
-        if usize::try_from(inst[2]).map_or(false, |dst| dst == f)
-            && (j == 1106 && arg == 0 || j == 1105 && arg != 0)
-        {
+        if usize::try_from(inst[2]) == Ok(f) && (j == 1106 && arg == 0 || j == 1105 && arg != 0) {
             Some(i)
         } else {
             None
@@ -75,7 +75,7 @@ fn parse_maze(s: &str) -> (Pos, Pos, PortalToPortal) {
                 .collect::<Vec<_>>()
         };
         let goal = |cand: Pos| cand != pos && portal_entrances.contains(&cand);
-        let goals = bfs(pos, std::usize::MAX, neigh, goal).goals;
+        let goals = bfs(pos, usize::MAX, neigh, goal).goals;
         (pos, goals.into_iter().map(|(g, d)| (g, d, 0)).collect())
     });
     let mut dists: PortalToPortal = dists.collect();
@@ -99,7 +99,7 @@ fn portals(s: &str) -> (HashSet<Pos>, Portals, HashSet<Pos>) {
             if cell == '.' {
                 dots.insert((y, x));
             }
-            if ('A'..='Z').contains(&cell) {
+            if cell.is_ascii_uppercase() {
                 use std::cmp::max;
                 letters.insert((y, x), cell);
                 max_y = max(max_y, y);
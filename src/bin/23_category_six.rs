@@ -93,7 +93,7 @@ impl Nic {
 
     fn send_packets(&mut self) -> Vec<Packet> {
         self.sent = true;
-        if self.rxs.iter().any(|&rx| rx == None) {
+        if self.rxs.iter().any(|&rx| rx.is_none()) {
             return Vec::new();
         }
         let rxs = self.rxs.iter().map(|&rx| rx.unwrap());
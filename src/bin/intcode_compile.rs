@@ -0,0 +1,16 @@
+use adventofcode::intcode::compile;
+
+fn main() {
+    let path = std::env::args().nth(1).expect("need source file");
+    let src = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+    match compile::compile(&src) {
+        Ok(mem) => {
+            let strs: Vec<_> = mem.iter().map(i64::to_string).collect();
+            println!("{}", strs.join(","));
+        }
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use adventofcode::intcode::format::{self, Format};
+use std::io::Write;
+
+fn main() {
+    let mem = adventofcode::read_input_file_or_intcode();
+    let format: Format = match std::env::args().nth(2) {
+        Some(f) => f.parse().unwrap_or_else(|e| panic!("{}", e)),
+        None => Format::Csv,
+    };
+    let stdout = std::io::stdout();
+    stdout
+        .lock()
+        .write_all(&format::write(&mem, format))
+        .unwrap();
+}
@@ -0,0 +1,37 @@
+use adventofcode::intcode::coverage::{Coverage, Kind};
+use adventofcode::intcode::symbols::Symbols;
+
+fn main() {
+    let mem = adventofcode::read_input_file_or_intcode();
+    let sets = std::env::args()
+        .skip(2)
+        .map(|a| adventofcode::intcode_inputs(&a));
+    let mut sets: Vec<_> = sets.collect();
+    if sets.is_empty() {
+        sets.push(Vec::new());
+    }
+
+    let mut cov = Coverage::default();
+    for set in &sets {
+        cov.run(&mem, set, 10_000_000);
+    }
+
+    let program_path = std::env::args().nth(1);
+    let syms = Symbols::for_program(program_path.as_deref());
+    print!("{}", cov.listing(&mem, &syms));
+    for fault in &cov.faults {
+        println!("{}", fault);
+    }
+
+    let kinds: Vec<_> = (0..mem.len()).map(|a| cov.get(a).kind()).collect();
+    let count = |kind: Kind| kinds.iter().filter(|&&k| k == kind).count();
+    println!(
+        "{} steps over {} input sets; of {} cells: {} code, {} data, {} unreached",
+        cov.steps,
+        sets.len(),
+        mem.len(),
+        count(Kind::Code),
+        count(Kind::Data),
+        count(Kind::Unreached),
+    );
+}
@@ -0,0 +1,56 @@
+use adventofcode::intcode::diff::MODES;
+use adventofcode::intcode::fuzz::{self, Config, Program, Rng};
+
+fn arg_or<T: std::str::FromStr>(i: usize, default: T) -> T {
+    std::env::args()
+        .nth(i)
+        .map_or(default, |a| a.parse().ok().expect("bad argument"))
+}
+
+fn csv(v: &[i64]) -> String {
+    let strs: Vec<_> = v.iter().map(i64::to_string).collect();
+    strs.join(",")
+}
+
+fn main() {
+    let seed = arg_or(1, 0);
+    let iterations = arg_or(2, 1000);
+    let size = arg_or(3, 20);
+    let config = Config {
+        budget: arg_or(4, 10_000),
+        compare_memory: arg_or(5, true),
+    };
+
+    // Faults are an expected outcome of random programs, not worth a backtrace each.
+    std::panic::set_hook(Box::new(|_| {}));
+
+    for iter in 0..iterations {
+        let mut rng = Rng::new(seed + iter);
+        let prog = fuzz::generate(&mut rng, size);
+        let (mode, divergence) = match fuzz::check(&prog, MODES, config) {
+            Some(d) => d,
+            None => continue,
+        };
+
+        let still_fails = |p: &Program| {
+            fuzz::check(p, MODES, config)
+                .is_some_and(|(m, d)| m == mode && d.same_kind(&divergence))
+        };
+        let small = fuzz::shrink(&prog, still_fails);
+        let (_, small_divergence) = fuzz::check(&small, MODES, config).expect("shrink lost it");
+
+        println!(
+            "seed {}: {} vs {}",
+            seed + iter,
+            MODES[0].name,
+            MODES[mode].name
+        );
+        println!("original ({} cells): {:?}", prog.mem.len(), divergence);
+        println!("shrunk ({} cells): {:?}", small.mem.len(), small_divergence);
+        println!("program: {}", csv(&small.mem));
+        println!("inputs: {}", csv(&small.inputs));
+        std::process::exit(1);
+    }
+
+    println!("no divergence in {} programs", iterations);
+}
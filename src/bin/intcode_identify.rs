@@ -0,0 +1,21 @@
+use adventofcode::intcode::fingerprint::{self, THRESHOLD};
+
+fn main() {
+    let mem = adventofcode::read_input_file_or_intcode();
+    let matches = fingerprint::identify(&mem);
+
+    for m in &matches {
+        println!(
+            "{:.2} day {:02} {}",
+            m.confidence, m.family.day, m.family.name
+        );
+    }
+
+    match matches.first() {
+        Some(m) if m.confidence >= THRESHOLD => println!("{}", m.family.day),
+        _ => {
+            println!("unrecognised");
+            std::process::exit(1);
+        }
+    }
+}
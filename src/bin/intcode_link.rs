@@ -0,0 +1,21 @@
+use adventofcode::intcode::stdlib;
+
+fn main() {
+    let mut mem = adventofcode::read_input_file_or_intcode();
+    let names: Vec<_> = std::env::args().skip(2).collect();
+    let names: Vec<_> = names.iter().map(String::as_str).collect();
+    let routines = stdlib::routines(&names).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    let entries = stdlib::link(&mut mem, &routines);
+
+    let strs: Vec<_> = mem.iter().map(i64::to_string).collect();
+    println!("{}", strs.join(","));
+    // Where each routine went, as a symbols file would have it.
+    let ends = entries.iter().skip(1).map(|&(_, start)| start);
+    let ends = ends.chain(std::iter::once(mem.len()));
+    for ((name, start), end) in entries.iter().zip(ends) {
+        eprintln!("{}..{} {}", start, end, name);
+    }
+}
@@ -0,0 +1,25 @@
+use adventofcode::intcode::lint;
+use adventofcode::intcode::symbols::Symbols;
+
+fn main() {
+    let mem = adventofcode::read_input_file_or_intcode();
+    let args: Vec<_> = std::env::args().collect();
+    let syms = Symbols::for_program(args.get(1).map(String::as_str));
+
+    let findings = lint::lint(&mem);
+    for f in &findings {
+        let at = syms.addr(f.pos);
+        let finding = f.to_string();
+        let problem = finding.split_once(": ").map_or("", |(_, p)| p);
+        println!("{}: {}", at, problem);
+    }
+    println!(
+        "{} problem{} in {} reachable instructions",
+        findings.len(),
+        if findings.len() == 1 { "" } else { "s" },
+        lint::reachable(&mem).len()
+    );
+    if !findings.is_empty() {
+        std::process::exit(1);
+    }
+}
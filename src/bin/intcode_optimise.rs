@@ -0,0 +1,28 @@
+use adventofcode::intcode::optimise;
+
+fn main() {
+    let mem = adventofcode::read_input_file_or_intcode();
+    let optimised = optimise::optimise(&mem).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    // Each set of inputs is a run to check the two against each other.
+    let sets = std::env::args()
+        .skip(2)
+        .map(|a| adventofcode::intcode_inputs(&a));
+    for (i, set) in sets.enumerate() {
+        match optimise::check(&mem, &optimised, &set, 100_000_000) {
+            Ok((before, after)) => eprintln!("inputs {}: {} steps -> {}", i + 1, before, after),
+            Err(divergence) => {
+                eprintln!("inputs {}: differs: {:?}", i + 1, divergence);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let changed = mem.iter().zip(&optimised).filter(|(a, b)| a != b).count();
+    eprintln!("{} cells changed", changed);
+    let strs: Vec<_> = optimised.iter().map(i64::to_string).collect();
+    println!("{}", strs.join(","));
+}
@@ -0,0 +1,25 @@
+use adventofcode::intcode::patch;
+use adventofcode::intcode::symbols::Symbols;
+
+fn main() {
+    let mut mem = adventofcode::read_input_file_or_intcode();
+    let args: Vec<_> = std::env::args().collect();
+    let path = args.get(2).expect("need patch file");
+    let patches = patch::load(path).unwrap_or_else(|e| panic!("{}", e));
+    let syms = Symbols::for_program(args.get(1).map(String::as_str));
+
+    let names: Vec<&str> = match args.get(3..) {
+        Some(names) if !names.is_empty() => names.iter().map(String::as_str).collect(),
+        _ => patches.iter().map(|p| p.name.as_str()).collect(),
+    };
+    for name in names {
+        let p = patch::find(&patches, name).unwrap_or_else(|| panic!("no patch {}", name));
+        if let Err(conflict) = p.apply_to_image(&mut mem) {
+            eprintln!("{}", conflict.render(&syms));
+            std::process::exit(1);
+        }
+    }
+
+    let strs: Vec<_> = mem.iter().map(i64::to_string).collect();
+    println!("{}", strs.join(","));
+}
@@ -0,0 +1,34 @@
+use adventofcode::intcode::executor::{self, Channel, Executor};
+use adventofcode::intcode::Computer;
+
+fn main() {
+    let mem = adventofcode::read_input_file_or_intcode();
+    let phases = std::env::args().nth(2).expect("need phases");
+    let phases = adventofcode::intcode_inputs(&phases);
+
+    // Machine i reads from channel i and sends to the next, the last feeding back to the first.
+    let channels: Vec<Channel<i64>> = phases.iter().map(|_| Channel::new()).collect();
+    let mut ex = Executor::new();
+    for (i, &phase) in phases.iter().enumerate() {
+        let (input, output) = (&channels[i], &channels[(i + 1) % channels.len()]);
+        input.send(phase);
+        let mut ic = Computer::new(&mem);
+        ic.trap_crashes();
+        let machine = executor::run(ic, input.clone(), output.clone());
+        ex.spawn(async move {
+            if let Some(fault) = machine.await.fault() {
+                eprintln!("machine {}: {}", i, fault);
+            }
+        });
+    }
+    channels[0].send(0);
+    ex.run_until_stalled();
+
+    if ex.pending() > 0 {
+        eprintln!("{} machines still waiting for input", ex.pending());
+    }
+    // What the last machine sent after the first stopped listening.
+    while let Some(v) = channels[0].try_recv() {
+        println!("{}", v);
+    }
+}
@@ -0,0 +1,195 @@
+use adventofcode::intcode::format::{self, Format};
+use adventofcode::intcode::lint;
+use adventofcode::intcode::session::Session;
+use adventofcode::intcode::symbols::Symbols;
+use adventofcode::intcode::{Cell, Computer, Limits};
+use std::collections::VecDeque;
+use std::io::{BufRead, Write};
+
+// Exit codes, besides 0 for halting normally.
+const FAULTED: i32 = 1;
+const STARVED: i32 = 2;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    // One value per line each way.
+    Numeric,
+    // Text each way; outputs outside ASCII go on lines of their own.
+    Ascii,
+}
+
+// The inputs a line of text stands for in this mode.
+fn inputs<T: Cell + std::str::FromStr>(mode: Mode, line: &str) -> Vec<T> {
+    match mode {
+        Mode::Numeric => line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|w| !w.is_empty())
+            .map(|w| w.parse().unwrap_or_else(|_| panic!("not a number: {}", w)))
+            .collect(),
+        Mode::Ascii => line
+            .bytes()
+            .chain([10])
+            .map(|b| T::from(i32::from(b)))
+            .collect(),
+    }
+}
+
+// Values as i64, if they all fit.
+fn narrow<T: Cell>(mem: &[T]) -> Option<Vec<i64>> {
+    mem.iter().map(|v| v.to_i64()).collect()
+}
+
+// Saves memory to $INTCODE_DUMP if set, in the format its name says,
+// or the puzzle's format if any value is too wide for the others.
+fn dump<T: Cell>(ic: &Computer<T>) {
+    if let Ok(path) = std::env::var("INTCODE_DUMP") {
+        let mem = ic.dump();
+        let bytes = match narrow(&mem) {
+            Some(mem) => format::write(&mem, Format::for_path(&path)),
+            None => {
+                let strs: Vec<_> = mem.iter().map(T::to_string).collect();
+                (strs.join(",") + "\n").into_bytes()
+            }
+        };
+        std::fs::write(&path, bytes).unwrap_or_else(|e| panic!("{}: {}", path, e));
+    }
+}
+
+// Saves every input read and output written to $INTCODE_RECORD if set, as a session for intcode_session to replay.
+fn save_session<T: Cell>(ic: &Computer<T>) {
+    if let Ok(path) = std::env::var("INTCODE_RECORD") {
+        let session = Session::from_journal(ic).to_string();
+        std::fs::write(&path, session).unwrap_or_else(|e| panic!("{}: {}", path, e));
+    }
+}
+
+// Caps from $INTCODE_LIMITS, such as addrs=65536,written=4096,output=1000,inputs=100; unset means none.
+fn limits() -> Limits {
+    let mut limits = Limits::default();
+    let spec = match std::env::var("INTCODE_LIMITS") {
+        Ok(spec) => spec,
+        Err(_) => return limits,
+    };
+    for cap in spec.split(',').filter(|c| !c.is_empty()) {
+        let (name, n) = cap
+            .split_once('=')
+            .unwrap_or_else(|| panic!("bad limit {}, want name=number", cap));
+        let n = n
+            .parse()
+            .unwrap_or_else(|_| panic!("bad limit {}, want name=number", cap));
+        let limit = match name {
+            "addrs" => &mut limits.addrs,
+            "written" => &mut limits.written,
+            "output" => &mut limits.output,
+            "inputs" => &mut limits.input_reads,
+            _ => panic!(
+                "unknown limit {}, want addrs, written, output, or inputs",
+                name
+            ),
+        };
+        *limit = Some(n);
+    }
+    limits
+}
+
+fn main() {
+    let args: Vec<_> = std::env::args().collect();
+    let syms = Symbols::for_program(args.get(1).map(String::as_str));
+    let mode = match args.get(2).map(String::as_str) {
+        None | Some("numeric") => Mode::Numeric,
+        Some("ascii") => Mode::Ascii,
+        Some(m) => panic!("unknown mode {}, want numeric or ascii", m),
+    };
+    let script = args
+        .get(3)
+        .map(|path| std::fs::read_to_string(path).unwrap_or_else(|e| panic!("{}: {}", path, e)));
+
+    // Wider cells for programs whose values outgrow an i64.
+    match std::env::var("INTCODE_CELL").as_deref() {
+        Err(_) | Ok("i64") => run(
+            &adventofcode::read_input_file_or_intcode(),
+            mode,
+            &syms,
+            script,
+        ),
+        Ok("i128") => run(
+            &adventofcode::read_input_file_or_intcode_as::<i128>(),
+            mode,
+            &syms,
+            script,
+        ),
+        Ok(c) => panic!("unknown cell {}, want i64 or i128", c),
+    }
+}
+
+fn run<T: Cell + std::str::FromStr>(mem: &[T], mode: Mode, syms: &Symbols, script: Option<String>) {
+    // The script's lines go first, then stdin's.
+    let mut pending = VecDeque::new();
+    for line in script.iter().flat_map(|s| s.lines()) {
+        pending.extend(inputs::<T>(mode, line));
+    }
+    let mut stdin = std::io::stdin().lock().lines();
+
+    // Lint only knows images that fit in an i64.
+    for finding in narrow(mem).map_or_else(Vec::new, |mem| lint::lint(&mem)) {
+        eprintln!("warning: {}", finding);
+    }
+
+    let mut ic = Computer::new(mem);
+    ic.trap_crashes();
+    ic.checked();
+    ic.limit(limits());
+    if std::env::var_os("INTCODE_RECORD").is_some() {
+        ic.record();
+    }
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    let mut at_line_start = true;
+
+    loop {
+        ic.cont();
+        for v in ic.output.drain(..) {
+            match (mode, v.to_i64()) {
+                (Mode::Ascii, Some(c)) if (0..128).contains(&c) => {
+                    out.write_all(&[c as u8]).unwrap();
+                    at_line_start = c == 10;
+                }
+                _ => {
+                    if !at_line_start {
+                        writeln!(out).unwrap();
+                    }
+                    writeln!(out, "{}", v).unwrap();
+                    at_line_start = true;
+                }
+            }
+        }
+        out.flush().unwrap();
+
+        if ic.is_halted() {
+            dump(&ic);
+            save_session(&ic);
+            if ic.fault().is_some() {
+                eprintln!("{}", ic.report().render(syms));
+                std::process::exit(FAULTED);
+            }
+            return;
+        }
+
+        while pending.is_empty() {
+            match stdin.next() {
+                Some(line) => {
+                    pending.extend(inputs::<T>(mode, &line.expect("couldn't read stdin")))
+                }
+                None => {
+                    eprintln!("out of input at {}", syms.addr(ic.pos()));
+                    dump(&ic);
+                    save_session(&ic);
+                    std::process::exit(STARVED);
+                }
+            }
+        }
+        while let Some(v) = pending.pop_front() {
+            ic.push_input(v);
+        }
+    }
+}
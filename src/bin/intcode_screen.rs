@@ -0,0 +1,55 @@
+use adventofcode::intcode::screen::{Palette, Screen};
+use adventofcode::intcode::Computer;
+
+// Once the given inputs run out, 0 (day 13's neutral joystick) is sent, for at most this many frames.
+const MAX_FRAMES: usize = 10_000;
+
+enum Sink {
+    // Redraws in place.
+    Terminal,
+    // frame_00000.txt and so on.
+    Directory(String),
+}
+
+fn main() {
+    let mem = adventofcode::read_input_file_or_intcode();
+    let args: Vec<_> = std::env::args().collect();
+    let sink = match args.get(2).map(String::as_str) {
+        None | Some("terminal") => Sink::Terminal,
+        Some(dir) => {
+            std::fs::create_dir_all(dir).unwrap_or_else(|e| panic!("{}: {}", dir, e));
+            Sink::Directory(dir.to_string())
+        }
+    };
+    let palette = match std::env::var("INTCODE_PALETTE") {
+        Ok(chars) => Palette::from_chars(&chars),
+        Err(_) => Palette::default(),
+    };
+    let mut inputs = args
+        .iter()
+        .skip(3)
+        .flat_map(|a| adventofcode::intcode_inputs(a));
+
+    let mut ic = Computer::new(&mem);
+    let mut screen = Screen::new(palette);
+    // Each time the program waits for input, or halts, is a frame.
+    for frame in 0..MAX_FRAMES {
+        ic.cont();
+        screen.take(&mut ic);
+        let drawn = screen.render();
+        match &sink {
+            Sink::Terminal => {
+                print!("\x1b[H\x1b[2J{}", drawn);
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            Sink::Directory(dir) => {
+                let path = format!("{}/frame_{:05}.txt", dir, frame);
+                std::fs::write(&path, drawn).unwrap_or_else(|e| panic!("{}: {}", path, e));
+            }
+        }
+        if ic.is_halted() {
+            return;
+        }
+        ic.push_input(inputs.next().unwrap_or(0));
+    }
+}
@@ -0,0 +1,42 @@
+use adventofcode::intcode::session::{self, Session};
+use adventofcode::intcode::symbols::Symbols;
+use adventofcode::intcode::Computer;
+
+fn main() {
+    let mem = adventofcode::read_input_file_or_intcode();
+    let args: Vec<_> = std::env::args().collect();
+    let mode = args.get(2).expect("need record or replay");
+    let path = args.get(3).expect("need session file");
+
+    let syms = Symbols::for_program(args.get(1).map(String::as_str));
+
+    let mut ic = Computer::new(&mem);
+    match mode.as_str() {
+        "record" => {
+            ic.record();
+            ic.cont();
+            for arg in &args[4..] {
+                for input in adventofcode::intcode_inputs(arg) {
+                    ic.cont_in(input);
+                }
+            }
+            let session = Session::from_journal(&ic);
+            std::fs::write(path, session.to_string()).expect("couldn't write session");
+            println!("recorded {} events", session.events.len());
+        }
+        "replay" => {
+            let session = Session::load(path).unwrap_or_else(|e| panic!("{}", e));
+            match session::replay(&mut ic, &session) {
+                Ok(()) => println!("replayed {} events", session.events.len()),
+                Err(mismatch) => {
+                    println!("{}", mismatch.render(&syms));
+                    for frame in ic.backtrace() {
+                        println!("  in {}", syms.frame(&frame));
+                    }
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => panic!("unknown mode {}", mode),
+    }
+}
@@ -0,0 +1,32 @@
+use adventofcode::intcode::symbolic::Machine;
+use adventofcode::intcode::symbols::Symbols;
+
+fn main() {
+    let mem = adventofcode::read_input_file_or_intcode();
+    let args: Vec<_> = std::env::args().collect();
+    let syms = Symbols::for_program(args.get(1).map(String::as_str));
+
+    let mut m = Machine::new(&mem);
+    let result = match args.get(2) {
+        // A function, by address or by name, and how many arguments it takes.
+        Some(func) => {
+            let addr = func.parse().unwrap_or_else(|_| {
+                let sym = (0..mem.len()).find(|&a| syms.describe(a).as_deref() == Some(func));
+                sym.unwrap_or_else(|| panic!("no function {}", func))
+            });
+            let n = args
+                .get(3)
+                .map_or(1, |n| n.parse().expect("bad argument count"));
+            m.call(addr, n).map(|ret| println!("returns {}", ret))
+        }
+        None => m.run(),
+    };
+
+    for (i, out) in m.output.iter().enumerate() {
+        println!("output {}: {}", i, out);
+    }
+    if let Err(stuck) = result {
+        println!("{}", stuck);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,64 @@
+use adventofcode::intcode::symbols::Symbols;
+use adventofcode::intcode::watch::{Access, Action, On, Watcher};
+use adventofcode::intcode::Computer;
+
+// addr or start..end, optionally followed by :r or :w (default both)
+fn watchpoint(spec: &str) -> (std::ops::Range<usize>, On) {
+    let (range, on) = match spec.split_once(':') {
+        Some((range, "r")) => (range, On::Read),
+        Some((range, "w")) => (range, On::Write),
+        Some((range, "rw")) => (range, On::Both),
+        Some((_, on)) => panic!("bad access {}", on),
+        None => (spec, On::Both),
+    };
+    let parse = |s: &str| s.parse::<usize>().expect("bad address");
+    let range = match range.split_once("..") {
+        Some((start, end)) => parse(start)..parse(end),
+        None => parse(range)..(parse(range) + 1),
+    };
+    (range, on)
+}
+
+fn main() {
+    let mem = adventofcode::read_input_file_or_intcode();
+    let args: Vec<_> = std::env::args().collect();
+    let syms = Symbols::for_program(args.get(1).map(String::as_str));
+
+    let mut watcher = Watcher::default();
+    for spec in args.get(2).expect("no watchpoints").split(',') {
+        let (range, on) = watchpoint(spec);
+        watcher.add(range, on, Action::Log);
+    }
+
+    let mut ic = Computer::new(&mem);
+    ic.trap_crashes();
+    watcher.cont(&mut ic);
+    for arg in &args[3..] {
+        for input in adventofcode::intcode_inputs(arg) {
+            watcher.cont_in(&mut ic, input);
+        }
+    }
+
+    for hit in &watcher.log {
+        let what = match hit.access {
+            Access::Read(v) => format!("read {}", v),
+            Access::Write { old, new } => format!("write {} -> {}", old, new),
+        };
+        println!(
+            "step {:>9} at {}: {} {}",
+            hit.step,
+            syms.addr(hit.pos),
+            syms.addr(hit.addr),
+            what
+        );
+    }
+
+    if ic.fault().is_some() {
+        println!("{}", ic.report().render(&syms));
+    } else if ic.is_blocked() {
+        println!("waiting for input at {}", syms.addr(ic.pos()));
+        for frame in ic.backtrace() {
+            println!("  in {}", syms.frame(&frame));
+        }
+    }
+}
@@ -1,6 +1,57 @@
-use std::collections::HashMap;
+#[cfg(feature = "std")]
+pub mod compile;
+#[cfg(feature = "std")]
+pub mod coverage;
+#[cfg(feature = "std")]
+pub mod diff;
+#[cfg(feature = "std")]
+pub mod disasm;
+#[cfg(feature = "std")]
+pub mod executor;
+#[cfg(feature = "std")]
+pub mod explore;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod fingerprint;
+#[cfg(feature = "std")]
+pub mod format;
+#[cfg(feature = "std")]
+pub mod fuzz;
+#[cfg(feature = "std")]
+pub mod lint;
+pub mod memory;
+#[cfg(feature = "std")]
+pub mod optimise;
+#[cfg(feature = "std")]
+pub mod patch;
+#[cfg(feature = "std")]
+pub mod report;
+#[cfg(feature = "std")]
+pub mod screen;
+#[cfg(feature = "std")]
+pub mod session;
+#[cfg(feature = "std")]
+pub mod stdlib;
+#[cfg(feature = "std")]
+pub mod symbolic;
+#[cfg(feature = "std")]
+pub mod symbols;
+#[cfg(feature = "std")]
+pub mod watch;
 
-pub fn functions(mem: &[i64]) -> Vec<std::ops::Range<usize>> {
+// The tools above need std; the Computer below needs only alloc.
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+// For the funopt caches. Without std there's no HashMap, but cells are Ord anyway.
+#[cfg(feature = "std")]
+type Map<K, V> = std::collections::HashMap<K, V>;
+#[cfg(not(feature = "std"))]
+type Map<K, V> = alloc::collections::BTreeMap<K, V>;
+
+pub fn functions(mem: &[i64]) -> Vec<core::ops::Range<usize>> {
     let mut calls = Vec::new();
     let mut rets = Vec::new();
     for (i, inst) in mem.windows(2).enumerate() {
@@ -22,38 +73,289 @@ pub fn functions(mem: &[i64]) -> Vec<std::ops::Range<usize>> {
     rets.into_iter().filter_map(pair_ret).collect()
 }
 
+// Number of input params and whether there is an output param,
+// or None if the opcode is unknown. Modes are ignored.
+pub fn arity(opcode: i64) -> Option<(u8, bool)> {
+    match opcode % 100 {
+        1 | 2 => Some((2, true)),
+        3 => Some((0, true)),
+        4 => Some((1, false)),
+        5 | 6 => Some((2, false)),
+        7 | 8 => Some((2, true)),
+        9 => Some((1, false)),
+        99 => Some((0, false)),
+        _ => None,
+    }
+}
+
+// Total length of the instruction including the opcode.
+pub fn width(opcode: i64) -> Option<usize> {
+    arity(opcode).map(|(i, o)| 1 + usize::from(i) + usize::from(o))
+}
+
+// What a Computer's memory holds.
+// i64 is enough for the puzzles; i128 is for programs that work with larger numbers.
+pub trait Cell:
+    Copy
+    + Eq
+    + Ord
+    + core::hash::Hash
+    + core::fmt::Debug
+    + core::fmt::Display
+    + From<bool>
+    + From<i32>
+    + core::ops::Add<Output = Self>
+    + core::ops::Mul<Output = Self>
+{
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    fn to_i64(self) -> Option<i64>;
+    fn to_usize(self) -> Option<usize>;
+}
+
+macro_rules! cell {
+    ($t:ty) => {
+        impl Cell for $t {
+            fn checked_add(self, other: Self) -> Option<Self> {
+                <$t>::checked_add(self, other)
+            }
+            fn checked_mul(self, other: Self) -> Option<Self> {
+                <$t>::checked_mul(self, other)
+            }
+            fn to_i64(self) -> Option<i64> {
+                i64::try_from(self).ok()
+            }
+            fn to_usize(self) -> Option<usize> {
+                usize::try_from(self).ok()
+            }
+        }
+    };
+}
+
+cell!(i64);
+cell!(i128);
+
+// In all cases, pos is that of the faulting instruction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Fault {
+    // Only in checked mode: an add, multiply, relative base adjustment, or relative address overflowed.
+    Overflow { pos: usize },
+    // The rest only when the corresponding Limits are set.
+    AddressLimit { pos: usize, addr: usize },
+    WriteLimit { pos: usize },
+    OutputLimit { pos: usize },
+    InputLimit { pos: usize },
+    // These three panic instead, unless Computer::trap_crashes.
+    // Unknown opcode or mode, immediate output param, or excess mode digits.
+    BadInstruction { pos: usize },
+    // Negative (or unrepresentable) address to read or write.
+    BadAddress { pos: usize },
+    // Negative (or unrepresentable) jump target.
+    BadTarget { pos: usize },
+}
+
+impl Fault {
+    pub fn pos(&self) -> usize {
+        match *self {
+            Fault::Overflow { pos }
+            | Fault::AddressLimit { pos, .. }
+            | Fault::WriteLimit { pos }
+            | Fault::OutputLimit { pos }
+            | Fault::InputLimit { pos }
+            | Fault::BadInstruction { pos }
+            | Fault::BadAddress { pos }
+            | Fault::BadTarget { pos } => pos,
+        }
+    }
+
+    fn is_crash(&self) -> bool {
+        matches!(
+            self,
+            Fault::BadInstruction { .. } | Fault::BadAddress { .. } | Fault::BadTarget { .. }
+        )
+    }
+}
+
+impl core::fmt::Display for Fault {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Fault::Overflow { pos } => write!(f, "arithmetic overflow at {}", pos),
+            Fault::AddressLimit { pos, addr } => {
+                write!(f, "address {} out of bounds at {}", addr, pos)
+            }
+            Fault::WriteLimit { pos } => write!(f, "too many cells written at {}", pos),
+            Fault::OutputLimit { pos } => write!(f, "too much pending output at {}", pos),
+            Fault::InputLimit { pos } => write!(f, "too many inputs read at {}", pos),
+            Fault::BadInstruction { pos } => write!(f, "invalid instruction at {}", pos),
+            Fault::BadAddress { pos } => write!(f, "invalid address at {}", pos),
+            Fault::BadTarget { pos } => write!(f, "invalid jump target at {}", pos),
+        }
+    }
+}
+
+// Caps for running programs that can't be trusted not to exhaust memory.
+// None means unlimited, which is the default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Limits {
+    // Reads and writes must be to addresses below this.
+    pub addrs: Option<usize>,
+    // Distinct cells written, not counting the image itself.
+    pub written: Option<usize>,
+    // Outputs not yet taken out of Computer::output.
+    pub output: Option<usize>,
+    // Total inputs read over the Computer's lifetime.
+    pub input_reads: Option<usize>,
+}
+
+// How many recently executed positions a Computer remembers, for fault reports.
+pub const HISTORY: usize = 16;
+
+fn over(limit: Option<usize>, n: usize) -> bool {
+    limit.is_some_and(|l| n >= l)
+}
+
+// What the most recent step touched, for tools that watch execution.
+#[derive(Clone, Copy, Debug)]
+pub struct Access<T> {
+    pub pos: usize,
+    // Addresses and values read by position or relative params; immediates aren't reads.
+    pub reads: [Option<(usize, T)>; 2],
+    // Address written, and the value it had before.
+    pub write: Option<(usize, T)>,
+}
+
+// What Computer::record keeps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event<T> {
+    In(T),
+    Out(T),
+}
+
+// One active call, found by Computer::backtrace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame<T> {
+    // Address of the function's 109 N prologue.
+    pub function: usize,
+    pub ret: usize,
+    // As many as the call site stored before jumping; may include locals if it stored those too.
+    pub args: Vec<T>,
+}
+
+// The program a Computer starts from, which it never writes to.
+// Shared images let a Computer outlive (and leave the thread of) whoever loaded the program.
 #[derive(Clone, Debug)]
-pub struct Computer<'a> {
+enum Image<'a, T> {
+    Borrowed(&'a [T]),
+    Shared(Arc<[T]>),
+}
+
+impl<T> core::ops::Deref for Image<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            Image::Borrowed(mem) => mem,
+            Image::Shared(mem) => mem,
+        }
+    }
+}
+
+// A value that clones share until one of them changes it, which is when it's copied.
+// For a Computer's output, journal, and caches, so forking one doesn't copy them all up front.
+#[derive(Clone, Debug, Default)]
+pub struct CopyOnWrite<V>(Arc<V>);
+
+impl<V> core::ops::Deref for CopyOnWrite<V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        &self.0
+    }
+}
+
+impl<V: Clone> CopyOnWrite<V> {
+    // Copies only if a clone still shares it.
+    pub fn into_inner(self) -> V {
+        Arc::unwrap_or_clone(self.0)
+    }
+}
+
+impl<V: Clone> core::ops::DerefMut for CopyOnWrite<V> {
+    fn deref_mut(&mut self) -> &mut V {
+        Arc::make_mut(&mut self.0)
+    }
+}
+
+// Cloning is cheap, as memory, output, and the rest are shared until written,
+// for searches that fork a Computer per state.
+#[derive(Clone, Debug)]
+pub struct Computer<'a, T = i64> {
     pos: usize,
-    relative_base: i64,
-    romem: &'a [i64],
-    rwmem: HashMap<usize, i64>,
+    relative_base: T,
+    romem: Image<'a, T>,
+    rwmem: memory::Memory<T>,
     halt: bool,
     block: bool,
-    input: Vec<i64>,
-    pub output: Vec<i64>,
+    fault: Option<Fault>,
+    input: VecDeque<T>,
+    pub output: CopyOnWrite<Vec<T>>,
+
+    checked: bool,
+    trap_crashes: bool,
+    limits: Limits,
+    input_reads: usize,
+    last: Access<T>,
+    journal: Option<CopyOnWrite<Vec<Event<T>>>>,
+    history: [usize; HISTORY],
+    executed: usize,
 
     funopt: bool,
-    cached_funcalls: HashMap<(usize, i64), i64>,
-    inflight_funcalls: HashMap<i64, (usize, i64)>,
+    cached_funcalls: CopyOnWrite<Map<(usize, T), T>>,
+    inflight_funcalls: CopyOnWrite<Map<T, (usize, T)>>,
     prev_stored_ret_addr: bool,
 }
 
-impl<'a> Computer<'a> {
-    pub fn new(mem: &'a [i64]) -> Self {
+impl<T: Cell> Computer<'static, T> {
+    // Creating many of these only costs a reference count each,
+    // and they are Send so long as nothing else is borrowed.
+    pub fn shared(mem: Arc<[T]>) -> Self {
+        Self::with_image(Image::Shared(mem))
+    }
+}
+
+impl<'a, T: Cell> Computer<'a, T> {
+    pub fn new(mem: &'a [T]) -> Self {
+        Self::with_image(Image::Borrowed(mem))
+    }
+
+    fn with_image(romem: Image<'a, T>) -> Self {
         Self {
             pos: 0,
-            relative_base: 0,
-            romem: mem,
-            rwmem: HashMap::new(),
+            relative_base: T::from(0),
+            romem,
+            rwmem: memory::Memory::default(),
             halt: false,
             block: false,
-            input: Vec::new(),
-            output: Vec::new(),
+            fault: None,
+            input: VecDeque::new(),
+            output: CopyOnWrite::default(),
+
+            checked: false,
+            trap_crashes: false,
+            limits: Limits::default(),
+            input_reads: 0,
+            last: Access {
+                pos: 0,
+                reads: [None; 2],
+                write: None,
+            },
+            journal: None,
+            history: [0; HISTORY],
+            executed: 0,
 
             funopt: false,
-            cached_funcalls: HashMap::new(),
-            inflight_funcalls: HashMap::new(),
+            cached_funcalls: CopyOnWrite::default(),
+            inflight_funcalls: CopyOnWrite::default(),
             prev_stored_ret_addr: false,
         }
     }
@@ -66,64 +368,302 @@ impl<'a> Computer<'a> {
         self.halt
     }
 
-    pub fn get(&self, i: usize) -> i64 {
-        self.rwmem.get(&i).cloned().unwrap_or_else(|| {
+    pub fn is_blocked(&self) -> bool {
+        self.block
+    }
+
+    pub fn relative_base(&self) -> T {
+        self.relative_base
+    }
+
+    // Inputs pushed but not yet read.
+    pub fn pending_input(&self) -> impl Iterator<Item = T> + '_ {
+        self.input.iter().cloned()
+    }
+
+    // Positions of the last (up to) HISTORY instructions executed, oldest first.
+    // Includes the current one if it faulted.
+    pub fn history(&self) -> Vec<usize> {
+        let n = core::cmp::min(self.executed, HISTORY);
+        let first = self.executed - n;
+        (first..self.executed)
+            .map(|i| self.history[i % HISTORY])
+            .collect()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn report(&self) -> report::Report<T> {
+        report::Report::new(self)
+    }
+
+    pub fn last_access(&self) -> &Access<T> {
+        &self.last
+    }
+
+    // A faulted Computer is also halted.
+    pub fn fault(&self) -> Option<&Fault> {
+        self.fault.as_ref()
+    }
+
+    pub fn get(&self, i: usize) -> T {
+        self.rwmem.get(i).unwrap_or_else(|| {
             if i < self.romem.len() {
                 self.romem[i]
             } else {
-                0
+                T::from(0)
             }
         })
     }
 
-    pub fn set(&mut self, i: usize, v: i64) {
+    pub fn set(&mut self, i: usize, v: T) {
         self.rwmem.insert(i, v);
     }
 
+    // All of memory as far as the image or the last cell written, whichever is further.
+    pub fn dump(&self) -> Vec<T> {
+        let written = self.rwmem.max_index().map_or(0, |i| i + 1);
+        let len = core::cmp::max(self.romem.len(), written);
+        (0..len).map(|i| self.get(i)).collect()
+    }
+
+    // Cells that have been written, in address order.
+    // Includes cells written with the value they already had.
+    pub fn written(&self) -> impl Iterator<Item = (usize, T)> + '_ {
+        self.rwmem.iter()
+    }
+
+    // Active calls, innermost first, assuming the calling convention funopt_jumped does:
+    // the caller stores the return address at [rb] and arguments at [rb+1].., then jumps,
+    // and the function starts with 109 N and ends with 109 -N, 2106 0 0.
+    // Stops at the first frame whose return address doesn't follow a jump to its function,
+    // which is normally the top level.
+    pub fn backtrace(&self) -> Vec<Frame<T>> {
+        let mut frames = Vec::new();
+        let mut pos = self.pos;
+        let mut rb = match self.relative_base.to_usize() {
+            Some(rb) => rb,
+            None => return frames,
+        };
+        // Each frame but the innermost takes at least a return address and an argument or local off rb.
+        let max_outer = rb / 2;
+
+        while let Some((function, size)) = self.frame_at(pos) {
+            // Only the innermost frame can be at its 109 N or 109 -N; a frame further out that moved rb by nothing
+            // would walk the same frames forever.
+            if !frames.is_empty() && (size == 0 || frames.len() > max_outer) {
+                break;
+            }
+            let base = match rb.checked_sub(size) {
+                Some(base) => base,
+                None => break,
+            };
+            let ret = match self.get(base).to_usize() {
+                Some(ret) if self.calls_at(ret, function) => ret,
+                _ => break,
+            };
+            let args = (1..=self.args_stored_before(ret - 3))
+                .map(|i| self.get(base + i))
+                .collect();
+            frames.push(Frame {
+                function,
+                ret,
+                args,
+            });
+            // Back in the caller, whose rb is where the return address went.
+            pos = ret;
+            rb = base;
+        }
+        frames
+    }
+
+    fn op_at(&self, i: usize) -> Option<i64> {
+        self.get(i).to_i64()
+    }
+
+    // The prologue of the function containing pos, and how much it has moved rb by at pos:
+    // nothing yet at the 109 N itself, nothing any more once 109 -N has run.
+    fn frame_at(&self, pos: usize) -> Option<(usize, usize)> {
+        let start = (0..=pos)
+            .rev()
+            .find(|&i| self.op_at(i) == Some(109) && self.op_at(i + 1).is_some_and(|n| n > 0))?;
+        let n = self.op_at(start + 1)?;
+        let returning = matches!(self.op_at(pos), Some(2105 | 2106))
+            && pos >= 2
+            && self.op_at(pos - 2) == Some(109)
+            && self.op_at(pos - 1) == Some(-n);
+        let size = if pos == start || returning { 0 } else { n };
+        Some((start, size as usize))
+    }
+
+    // Whether the instruction just before ret is an unconditional jump to function.
+    fn calls_at(&self, ret: usize, function: usize) -> bool {
+        if ret < 3 || self.op_at(ret - 1) != Some(function as i64) {
+            return false;
+        }
+        match (self.op_at(ret - 3), self.op_at(ret - 2)) {
+            (Some(1105), Some(c)) => c != 0,
+            (Some(1106), Some(c)) => c == 0,
+            _ => false,
+        }
+    }
+
+    // How many argument cells the call sequence ending at jump filled in,
+    // judging by the run of relative-mode writes (add, mul, lt, eq) just before it.
+    fn args_stored_before(&self, jump: usize) -> usize {
+        let mut args = 0;
+        let mut at = jump;
+        while at >= 4 {
+            at -= 4;
+            let stores = match self.op_at(at) {
+                Some(op) => op / 10000 == 2 && matches!(op % 100, 1 | 2 | 7 | 8),
+                None => false,
+            };
+            if !stores {
+                break;
+            }
+            let offset = self.op_at(at + 3).unwrap_or(0);
+            args = core::cmp::max(args, offset.max(0) as usize);
+        }
+        args
+    }
+
     pub fn funopt(&mut self) {
         self.funopt = true;
     }
 
+    // Overflowing arithmetic, including on the relative base and relative addresses,
+    // faults instead of panicking (debug) or wrapping (release).
+    pub fn checked(&mut self) {
+        self.checked = true;
+    }
+
+    // From now on, keep every input read and output written, in order.
+    pub fn record(&mut self) {
+        self.journal.get_or_insert_with(CopyOnWrite::default);
+    }
+
+    pub fn journal(&self) -> &[Event<T>] {
+        self.journal.as_deref().map_or(&[], Vec::as_slice)
+    }
+
+    // Bad instructions, addresses, and targets fault rather than panicking with a report.
+    pub fn trap_crashes(&mut self) {
+        self.trap_crashes = true;
+    }
+
+    pub fn limit(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    fn trap(&mut self, fault: Fault) {
+        let crash = fault.is_crash();
+        self.fault = Some(fault);
+        self.halt = true;
+        if crash && !self.trap_crashes {
+            self.crash();
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn crash(&self) -> ! {
+        panic!("{}", self.report())
+    }
+
+    // Reports need symbols, which need std, so there's only the fault.
+    #[cfg(not(feature = "std"))]
+    fn crash(&self) -> ! {
+        panic!("{}", self.fault.as_ref().expect("just trapped"))
+    }
+
     pub fn step(&mut self) {
-        let opcode = self.get(self.pos);
-        let (num_params, v1, v2, aout) = self.params(opcode);
+        self.last = Access {
+            pos: self.pos,
+            reads: [None; 2],
+            write: None,
+        };
+        self.history[self.executed % HISTORY] = self.pos;
+        self.executed += 1;
+        let opcode = match self.get(self.pos).to_i64() {
+            Some(opcode) => opcode,
+            None => return self.trap(Fault::BadInstruction { pos: self.pos }),
+        };
+        let (num_params, v1, v2, aout) = match self.params(opcode) {
+            Ok(params) => params,
+            Err(fault) => return self.trap(fault),
+        };
 
         let mut just_stored_ret_addr = false;
         let mut jump = None;
+        let mut write = None;
 
         match opcode % 100 {
-            1 => {
-                self.set(aout, v1 + v2);
-                if let Ok(v) = usize::try_from(v1 + v2) {
-                    just_stored_ret_addr = v == self.pos + 7;
+            1 | 2 => {
+                let add = opcode % 100 == 1;
+                let checked = if add {
+                    v1.checked_add(v2)
+                } else {
+                    v1.checked_mul(v2)
+                };
+                let v = match checked {
+                    Some(v) => v,
+                    None if self.checked => return self.trap(Fault::Overflow { pos: self.pos }),
+                    // Let it overflow the way it always has.
+                    None if add => v1 + v2,
+                    None => v1 * v2,
+                };
+                write = Some(v);
+                just_stored_ret_addr = v.to_usize() == Some(self.pos + 7);
+            }
+            3 => {
+                if self.input.is_empty() {
+                    self.block = true;
+                    // It'll run again once there's input.
+                    self.executed -= 1;
+                } else if over(self.limits.input_reads, self.input_reads) {
+                    return self.trap(Fault::InputLimit { pos: self.pos });
+                } else {
+                    self.input_reads += 1;
+                    write = self.input.pop_front();
+                    if let (Some(j), Some(v)) = (&mut self.journal, write) {
+                        j.push(Event::In(v));
+                    }
                 }
             }
-            2 => {
-                self.set(aout, v1 * v2);
-                if let Ok(v) = usize::try_from(v1 * v2) {
-                    just_stored_ret_addr = v == self.pos + 7;
+            4 => {
+                if over(self.limits.output, self.output.len()) {
+                    return self.trap(Fault::OutputLimit { pos: self.pos });
+                }
+                if let Some(j) = &mut self.journal {
+                    j.push(Event::Out(v1));
                 }
+                self.output.push(v1)
             }
-            3 => match self.input.pop() {
-                Some(v) => self.set(aout, v),
-                None => self.block = true,
-            },
-            4 => self.output.push(v1),
             5 => {
-                if v1 != 0 {
+                if v1 != T::from(0) {
                     jump = Some(v2)
                 }
             }
             6 => {
-                if v1 == 0 {
+                if v1 == T::from(0) {
                     jump = Some(v2)
                 }
             }
-            7 => self.set(aout, i64::from(v1 < v2)),
-            8 => self.set(aout, i64::from(v1 == v2)),
-            9 => self.relative_base += v1,
+            7 => write = Some(T::from(v1 < v2)),
+            8 => write = Some(T::from(v1 == v2)),
+            9 => match self.sum(self.relative_base, v1) {
+                Ok(rb) => self.relative_base = rb,
+                Err(fault) => return self.trap(fault),
+            },
             99 => self.halt = true,
-            _ => panic!("Unknown opcode {}", opcode),
+            _ => unreachable!("params accepted opcode {}", opcode),
+        }
+
+        if let Some(v) = write {
+            if self.rwmem.get(aout).is_none() && over(self.limits.written, self.rwmem.len()) {
+                return self.trap(Fault::WriteLimit { pos: self.pos });
+            }
+            self.last.write = Some((aout, self.get(aout)));
+            self.set(aout, v);
         }
 
         if !self.block {
@@ -131,7 +671,10 @@ impl<'a> Computer<'a> {
                 if self.funopt {
                     j = self.funopt_jumped(j);
                 }
-                self.pos = usize::try_from(j).expect("invalid jump target");
+                match j.to_usize() {
+                    Some(j) => self.pos = j,
+                    None => return self.trap(Fault::BadTarget { pos: self.pos }),
+                }
             } else {
                 self.pos += 1 + usize::from(num_params);
             }
@@ -146,74 +689,104 @@ impl<'a> Computer<'a> {
         }
     }
 
-    pub fn cont_in(&mut self, input: i64) {
-        self.input.push(input);
-        self.block = false;
+    pub fn cont_in(&mut self, input: T) {
+        self.push_input(input);
         self.cont();
     }
 
-    fn in_param(&self, offset: usize, mode: i64) -> i64 {
-        let v = self.get(self.pos + offset) + i64::from(mode == 2) * self.relative_base;
+    // Queues an input without running; inputs are consumed in the order pushed.
+    pub fn push_input(&mut self, input: T) {
+        self.input.push_back(input);
+        self.block = false;
+    }
 
-        if mode == 1 {
-            return v;
+    fn addr(&self, v: T) -> Result<usize, Fault> {
+        let addr = v.to_usize().ok_or(Fault::BadAddress { pos: self.pos })?;
+        if over(self.limits.addrs, addr) {
+            return Err(Fault::AddressLimit {
+                pos: self.pos,
+                addr,
+            });
         }
-        if mode != 0 && mode != 2 {
-            panic!("unknown read mode {}", mode);
+        Ok(addr)
+    }
+
+    // a + b, or in checked mode an overflow fault if it doesn't fit.
+    fn sum(&self, a: T, b: T) -> Result<T, Fault> {
+        match a.checked_add(b) {
+            Some(v) => Ok(v),
+            None if self.checked => Err(Fault::Overflow { pos: self.pos }),
+            // Let it overflow the way it always has.
+            None => Ok(a + b),
         }
+    }
 
-        self.get(usize::try_from(v).expect("invalid read target"))
+    // The param's value, offset by the relative base in relative mode.
+    fn relative(&self, offset: usize, mode: i64) -> Result<T, Fault> {
+        let v = self.get(self.pos + offset);
+        if mode == 2 {
+            self.sum(v, self.relative_base)
+        } else {
+            Ok(v)
+        }
     }
 
-    fn params(&self, opcode: i64) -> (u8, i64, i64, usize) {
-        let (num_inputs, has_output) = match opcode % 100 {
-            1 | 2 => (2, true),
-            3 => (0, true),
-            4 => (1, false),
-            5 | 6 => (2, false),
-            7 | 8 => (2, true),
-            9 => (1, false),
-            99 => (0, false),
-            _ => panic!("Unknown opcode {}", opcode),
-        };
+    fn in_param(&mut self, offset: usize, mode: i64) -> Result<T, Fault> {
+        let v = self.relative(offset, mode)?;
+
+        if mode == 1 {
+            return Ok(v);
+        }
+        let addr = self.addr(v)?;
+        let v = self.get(addr);
+        self.last.reads[offset - 1] = Some((addr, v));
+        Ok(v)
+    }
+
+    fn params(&mut self, opcode: i64) -> Result<(u8, T, T, usize), Fault> {
+        let bad = Fault::BadInstruction { pos: self.pos };
+        let (num_inputs, has_output) = arity(opcode).ok_or(bad.clone())?;
         let num_params = num_inputs + u8::from(has_output);
 
         let mode_divisor = [100, 1_000, 10_000, 100_000];
 
         if opcode >= mode_divisor[usize::from(num_params)] {
-            panic!("excess modes for {}", opcode);
+            return Err(bad);
+        }
+        let read_modes = [(opcode / 100) % 10, (opcode / 1000) % 10];
+        if read_modes[..usize::from(num_inputs)].iter().any(|&m| m > 2) {
+            return Err(bad);
         }
 
         let v1 = if num_inputs >= 1 {
-            self.in_param(1, (opcode / 100) % 10)
+            self.in_param(1, read_modes[0])?
         } else {
-            0
+            T::from(0)
         };
         let v2 = if num_inputs >= 2 {
-            self.in_param(2, (opcode / 1000) % 10)
+            self.in_param(2, read_modes[1])?
         } else {
-            0
+            T::from(0)
         };
         let o = if has_output {
             let write_mode = (opcode / mode_divisor[usize::from(num_inputs)]) % 10;
 
             if write_mode != 0 && write_mode != 2 {
-                panic!("unknown write mode {}", write_mode);
+                return Err(bad);
             }
 
-            let v = self.get(self.pos + 1 + usize::from(num_inputs))
-                + i64::from(write_mode == 2) * self.relative_base;
-            usize::try_from(v).expect("invalid write target")
+            let v = self.relative(1 + usize::from(num_inputs), write_mode)?;
+            self.addr(v)?
         } else {
             0
         };
 
-        (num_params, v1, v2, o)
+        Ok((num_params, v1, v2, o))
     }
 
-    fn funopt_jumped(&mut self, jump_target: i64) -> i64 {
-        let rb = match usize::try_from(self.relative_base) {
-            Ok(rb) => rb,
+    fn funopt_jumped(&mut self, jump_target: T) -> T {
+        let rb = match self.relative_base.to_usize() {
+            Some(rb) => rb,
             _ => return jump_target,
         };
 
@@ -228,7 +801,10 @@ impl<'a> Computer<'a> {
 
         if self.prev_stored_ret_addr {
             // CALL
-            let uj = jump_target as usize;
+            let uj = match jump_target.to_usize() {
+                Some(uj) => uj,
+                None => return jump_target,
+            };
             let arg = self.get(rb + 1);
             if let Some(&cached_result) = self.cached_funcalls.get(&(uj, arg)) {
                 // Cached - hijack jump target
@@ -243,3 +819,55 @@ impl<'a> Computer<'a> {
         jump_target
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Crashes, then walks the stack, which must end with at most depth frames.
+    fn backtrace_ends(mem: &[i64], depth: usize) {
+        let mut ic = Computer::new(mem);
+        ic.trap_crashes();
+        ic.cont();
+        assert!(ic.fault().is_some_and(Fault::is_crash), "{:?}", ic.fault());
+        assert!(ic.backtrace().len() <= depth);
+    }
+
+    // In checked mode, faults at pos rather than wrapping.
+    fn overflows(mem: &[i64], pos: usize) {
+        let mut ic = Computer::new(mem);
+        ic.checked();
+        ic.cont();
+        assert_eq!(ic.fault(), Some(&Fault::Overflow { pos }));
+    }
+
+    // With i128 cells, the same program gets past where i64 would overflow.
+    fn wide(mem: &[i64], want: i128) {
+        let mem: Vec<i128> = mem.iter().map(|&v| i128::from(v)).collect();
+        let mut ic = Computer::new(&mem);
+        ic.checked();
+        ic.cont();
+        assert_eq!(ic.fault(), None);
+        assert_eq!(ic.output.to_vec(), [want]);
+    }
+
+    const MAX: i64 = i64::MAX;
+
+    crate::tests! {
+        overflows {
+            overflow_add(&[1101, MAX, 1, 5, 99, 0], 0);
+            overflow_mul(&[1102, MAX, 2, 5, 99, 0], 0);
+            overflow_relative_base(&[109, MAX, 109, 1, 99], 2);
+            overflow_relative_address(&[109, MAX, 204, 1, 99], 2);
+        }
+        wide {
+            wide_add(&[1101, MAX, 1, 7, 4, 7, 99, 0], i128::from(MAX) + 1);
+            wide_mul(&[1102, MAX, 2, 7, 4, 7, 99, 0], i128::from(MAX) * 2);
+            wide_relative_base(&[109, MAX, 109, 1, 204, -MAX, 99], i128::from(MAX));
+        }
+        backtrace_ends {
+            // [rb] points back at the function, which moved rb by nothing yet.
+            return_to_self(&[1101, 7, 0, 0, 1105, 1, 7, 109, 1, 42], 1);
+        }
+    }
+}
@@ -0,0 +1,1068 @@
+// Compiling a small structured language to intcode, for test workloads and demos.
+//
+//     # Factorials of each input until a 0.
+//     fn fact(n) {
+//         if n < 2 { return 1; }
+//         return n * fact(n - 1);
+//     }
+//     var n = input();
+//     while n != 0 {
+//         output fact(n);
+//         n = input();
+//     }
+//     output "done\n";
+//
+// All values are integers. Variables are declared with var; those outside any function are global.
+// Statements: var, assignment, if/else, while, return, output (of a value or a string's characters), halt,
+// and any expression, such as a call.
+// Expressions: numbers, variables, calls, input(), mem[addr] (also assignable), and in precedence order:
+// unary - and !; *, / and %; + and -; comparisons; &&; ||. Both sides of && and || are always evaluated.
+// / and % are calls to div and mod, which like any other function the program calls but doesn't define,
+// come from the standard library (stdlib.icl) if it has them.
+// # starts a comment.
+//
+// Functions use the calling convention the puzzle programs do, so backtraces work on the result:
+// the caller puts the return address at [rb] and arguments at [rb+1].., then jumps;
+// the function moves rb past its frame with 109 N, and leaves its result at [rb+1] after moving it back.
+//
+// Don't run the result with funopt, though: it caches on the function and its first argument alone,
+// so it gives wrong answers for functions with more arguments (including div and mod) or that read globals,
+// input or memory.
+//
+// The image is the top-level code (ending in a halt), then the functions, then the globals;
+// the stack starts right after.
+
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Num(i64),
+    Name(String),
+    Str(String),
+    Sym(&'static str),
+}
+
+// Longest first, so that == isn't read as two =.
+const SYMBOLS: &[&str] = &[
+    "==", "!=", "<=", ">=", "&&", "||", "<", ">", "+", "-", "*", "/", "%", "!", "=", "(", ")", "{",
+    "}", "[", "]", ",", ";",
+];
+
+const KEYWORDS: &[&str] = &[
+    "fn", "var", "if", "else", "while", "return", "output", "input", "mem", "halt",
+];
+
+// Each token with its line number.
+fn tokenize(src: &str) -> Result<Vec<(Token, usize)>, String> {
+    let mut tokens = Vec::new();
+    for (i, line) in src.lines().enumerate() {
+        let lineno = i + 1;
+        let mut rest = line;
+        loop {
+            rest = rest.trim_start();
+            if rest.is_empty() || rest.starts_with('#') {
+                break;
+            }
+            let c = rest.chars().next().unwrap();
+            if c.is_ascii_digit() {
+                let end = rest
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                let n = rest[..end]
+                    .parse()
+                    .map_err(|_| format!("line {}: number too big", lineno))?;
+                tokens.push((Token::Num(n), lineno));
+                rest = &rest[end..];
+            } else if c.is_ascii_alphabetic() || c == '_' {
+                let end = rest
+                    .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                    .unwrap_or(rest.len());
+                tokens.push((Token::Name(rest[..end].to_string()), lineno));
+                rest = &rest[end..];
+            } else if c == '"' {
+                let mut s = String::new();
+                let mut chars = rest[1..].char_indices();
+                let end = loop {
+                    match chars.next() {
+                        Some((j, '"')) => break j + 2,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, 'n')) => s.push('\n'),
+                            Some((_, c)) => s.push(c),
+                            None => return Err(format!("line {}: unterminated string", lineno)),
+                        },
+                        Some((_, c)) => s.push(c),
+                        None => return Err(format!("line {}: unterminated string", lineno)),
+                    }
+                };
+                tokens.push((Token::Str(s), lineno));
+                rest = &rest[end..];
+            } else {
+                let sym = SYMBOLS
+                    .iter()
+                    .find(|s| rest.starts_with(*s))
+                    .ok_or_else(|| format!("line {}: unexpected {}", lineno, c))?;
+                tokens.push((Token::Sym(sym), lineno));
+                rest = &rest[sym.len()..];
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Num(i64),
+    // With the line it's on, for reporting undefined names.
+    Var(String, usize),
+    Call(String, Vec<Expr>, usize),
+    Input,
+    Mem(Box<Expr>),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Stmt {
+    Var(String, Expr),
+    // The target is a Var or a Mem.
+    Assign(Expr, Expr),
+    If(Expr, Vec<Stmt>, Vec<Stmt>),
+    While(Expr, Vec<Stmt>),
+    Return(Option<Expr>, usize),
+    Output(Expr),
+    OutputStr(String),
+    Halt,
+    Expr(Expr),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Func {
+    pub name: String,
+    pub params: Vec<String>,
+    pub body: Vec<Stmt>,
+    pub line: usize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Program {
+    pub funcs: Vec<Func>,
+    pub main: Vec<Stmt>,
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    at: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.at).map(|(t, _)| t)
+    }
+
+    fn line(&self) -> usize {
+        let last = self.tokens.last().map_or(1, |&(_, l)| l);
+        self.tokens.get(self.at).map_or(last, |&(_, l)| l)
+    }
+
+    fn err<T>(&self, msg: &str) -> Result<T, String> {
+        let found = match self.peek() {
+            Some(Token::Num(n)) => n.to_string(),
+            Some(Token::Name(n)) => n.clone(),
+            Some(Token::Str(s)) => format!("{:?}", s),
+            Some(Token::Sym(s)) => s.to_string(),
+            None => "end of input".to_string(),
+        };
+        Err(format!(
+            "line {}: expected {}, found {}",
+            self.line(),
+            msg,
+            found
+        ))
+    }
+
+    fn is_sym(&self, sym: &str) -> bool {
+        match self.peek() {
+            Some(Token::Sym(s)) => *s == sym,
+            Some(Token::Name(n)) => n == sym,
+            _ => false,
+        }
+    }
+
+    fn accept(&mut self, sym: &str) -> bool {
+        let found = self.is_sym(sym);
+        if found {
+            self.at += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, sym: &str) -> Result<(), String> {
+        if self.accept(sym) {
+            Ok(())
+        } else {
+            self.err(sym)
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some(Token::Name(n)) if !KEYWORDS.contains(&n.as_str()) => {
+                let n = n.clone();
+                self.at += 1;
+                Ok(n)
+            }
+            _ => self.err("a name"),
+        }
+    }
+
+    fn program(&mut self) -> Result<Program, String> {
+        let mut prog = Program::default();
+        while self.peek().is_some() {
+            if self.is_sym("fn") {
+                prog.funcs.push(self.func()?);
+            } else {
+                prog.main.push(self.stmt()?);
+            }
+        }
+        Ok(prog)
+    }
+
+    fn func(&mut self) -> Result<Func, String> {
+        let line = self.line();
+        self.expect("fn")?;
+        let name = self.name()?;
+        self.expect("(")?;
+        let mut params = Vec::new();
+        if !self.accept(")") {
+            loop {
+                params.push(self.name()?);
+                if self.accept(")") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+        let body = self.block()?;
+        Ok(Func {
+            name,
+            params,
+            body,
+            line,
+        })
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>, String> {
+        self.expect("{")?;
+        let mut stmts = Vec::new();
+        while !self.accept("}") {
+            if self.peek().is_none() {
+                return self.err("}");
+            }
+            stmts.push(self.stmt()?);
+        }
+        Ok(stmts)
+    }
+
+    fn stmt(&mut self) -> Result<Stmt, String> {
+        let line = self.line();
+        let stmt = if self.accept("var") {
+            let name = self.name()?;
+            self.expect("=")?;
+            Stmt::Var(name, self.expr()?)
+        } else if self.accept("if") {
+            let cond = self.expr()?;
+            let then = self.block()?;
+            let otherwise = if !self.accept("else") {
+                Vec::new()
+            } else if self.is_sym("if") {
+                vec![self.stmt()?]
+            } else {
+                self.block()?
+            };
+            return Ok(Stmt::If(cond, then, otherwise));
+        } else if self.accept("while") {
+            let cond = self.expr()?;
+            return Ok(Stmt::While(cond, self.block()?));
+        } else if self.accept("return") {
+            let value = if self.is_sym(";") {
+                None
+            } else {
+                Some(self.expr()?)
+            };
+            Stmt::Return(value, line)
+        } else if self.accept("output") {
+            match self.peek() {
+                Some(Token::Str(s)) => {
+                    let s = s.clone();
+                    self.at += 1;
+                    Stmt::OutputStr(s)
+                }
+                _ => Stmt::Output(self.expr()?),
+            }
+        } else if self.accept("halt") {
+            Stmt::Halt
+        } else {
+            let e = self.expr()?;
+            if self.accept("=") {
+                if !matches!(e, Expr::Var(..) | Expr::Mem(_)) {
+                    return Err(format!(
+                        "line {}: can only assign to variables and mem",
+                        line
+                    ));
+                }
+                Stmt::Assign(e, self.expr()?)
+            } else {
+                Stmt::Expr(e)
+            }
+        };
+        self.expect(";")?;
+        Ok(stmt)
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        self.binary(0)
+    }
+
+    // Operators at each precedence level, loosest first.
+    fn binary(&mut self, level: usize) -> Result<Expr, String> {
+        const LEVELS: &[&[(&str, BinOp)]] = &[
+            &[("||", BinOp::Or)],
+            &[("&&", BinOp::And)],
+            &[
+                ("==", BinOp::Eq),
+                ("!=", BinOp::Ne),
+                ("<=", BinOp::Le),
+                (">=", BinOp::Ge),
+                ("<", BinOp::Lt),
+                (">", BinOp::Gt),
+            ],
+            &[("+", BinOp::Add), ("-", BinOp::Sub)],
+            &[("*", BinOp::Mul)],
+        ];
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+        let mut e = self.binary(level + 1)?;
+        'more: loop {
+            // Division is left to the standard library.
+            if level == LEVELS.len() - 1 {
+                for (sym, func) in [("/", "div"), ("%", "mod")] {
+                    let line = self.line();
+                    if self.accept(sym) {
+                        let rhs = self.binary(level + 1)?;
+                        e = Expr::Call(func.to_string(), vec![e, rhs], line);
+                        continue 'more;
+                    }
+                }
+            }
+            for &(sym, op) in LEVELS[level] {
+                if self.accept(sym) {
+                    let rhs = self.binary(level + 1)?;
+                    e = Expr::Binary(op, Box::new(e), Box::new(rhs));
+                    continue 'more;
+                }
+            }
+            return Ok(e);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.accept("-") {
+            return Ok(match self.unary()? {
+                Expr::Num(n) => Expr::Num(-n),
+                e => Expr::Neg(Box::new(e)),
+            });
+        }
+        if self.accept("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let line = self.line();
+        if let Some(&Token::Num(n)) = self.peek() {
+            self.at += 1;
+            return Ok(Expr::Num(n));
+        }
+        if self.accept("(") {
+            let e = self.expr()?;
+            self.expect(")")?;
+            return Ok(e);
+        }
+        if self.accept("input") {
+            self.expect("(")?;
+            self.expect(")")?;
+            return Ok(Expr::Input);
+        }
+        if self.accept("mem") {
+            self.expect("[")?;
+            let addr = self.expr()?;
+            self.expect("]")?;
+            return Ok(Expr::Mem(Box::new(addr)));
+        }
+        let name = self.name().or_else(|_| self.err("an expression"))?;
+        if !self.accept("(") {
+            return Ok(Expr::Var(name, line));
+        }
+        let mut args = Vec::new();
+        if !self.accept(")") {
+            loop {
+                args.push(self.expr()?);
+                if self.accept(")") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+        Ok(Expr::Call(name, args, line))
+    }
+}
+
+pub fn parse(src: &str) -> Result<Program, String> {
+    let mut parser = Parser {
+        tokens: tokenize(src)?,
+        at: 0,
+    };
+    parser.program()
+}
+
+// Where a value lives, as far as an instruction's param is concerned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Opnd {
+    Imm(i64),
+    // A fixed address known already.
+    Abs(usize),
+    // A label's address, and the cell there; these move with the code, unlike Abs.
+    Label(usize),
+    AtLabel(usize),
+    Global(usize),
+    // Slot in the current function's frame; its rb offset is only known once the frame size is.
+    Slot(usize),
+    // [rb+k] as it stands, for passing arguments to a call.
+    Out(i64),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Fix {
+    Label(usize),
+    Func(usize),
+    Global(usize),
+    Stack,
+    // Frame-relative; resolved at the end of each function.
+    Slot(usize),
+    FrameSize,
+    NegFrameSize,
+}
+
+struct Gen<'p> {
+    prog: &'p Program,
+    code: Vec<i64>,
+    fixes: Vec<(usize, Fix)>,
+    labels: Vec<Option<usize>>,
+    // Named globals first, then the top level's temporaries.
+    globals: Vec<String>,
+    funcs: HashMap<&'p str, usize>,
+
+    // Variables of the function being compiled, by slot; empty at the top level.
+    locals: Vec<String>,
+    in_func: bool,
+    temps: usize,
+    max_temps: usize,
+}
+
+// Slot 0 is the return address, then the params, of which the first also holds the result.
+fn first_local(num_params: usize) -> usize {
+    1 + std::cmp::max(num_params, 1)
+}
+
+// Every var declared in these statements, including inside blocks, in order.
+fn declared(stmts: &[Stmt], names: &mut Vec<String>) {
+    for s in stmts {
+        match s {
+            Stmt::Var(name, _) if !names.contains(name) => names.push(name.clone()),
+            Stmt::If(_, a, b) => {
+                declared(a, names);
+                declared(b, names);
+            }
+            Stmt::While(_, body) => declared(body, names),
+            _ => {}
+        }
+    }
+}
+
+impl<'p> Gen<'p> {
+    fn label(&mut self) -> usize {
+        self.labels.push(None);
+        self.labels.len() - 1
+    }
+
+    fn place(&mut self, label: usize) {
+        self.labels[label] = Some(self.code.len());
+    }
+
+    // A label this many cells ahead.
+    fn ahead(&mut self, cells: usize) -> usize {
+        let label = self.label();
+        self.labels[label] = Some(self.code.len() + cells);
+        label
+    }
+
+    fn fix(&mut self, f: Fix) {
+        self.fixes.push((self.code.len(), f));
+        self.code.push(0);
+    }
+
+    fn mode(p: Opnd) -> i64 {
+        match p {
+            Opnd::Imm(_) | Opnd::Label(_) => 1,
+            Opnd::Abs(_) | Opnd::AtLabel(_) | Opnd::Global(_) => 0,
+            Opnd::Slot(_) | Opnd::Out(_) => 2,
+        }
+    }
+
+    fn param(&mut self, p: Opnd) {
+        match p {
+            Opnd::Imm(v) | Opnd::Out(v) => self.code.push(v),
+            Opnd::Abs(a) => self.code.push(a as i64),
+            Opnd::Label(l) | Opnd::AtLabel(l) => self.fix(Fix::Label(l)),
+            Opnd::Global(g) => self.fix(Fix::Global(g)),
+            Opnd::Slot(s) => self.fix(Fix::Slot(s)),
+        }
+    }
+
+    fn emit(&mut self, op: i64, params: &[Opnd]) {
+        let mut opcode = op;
+        let mut divisor = 100;
+        for &p in params {
+            opcode += Self::mode(p) * divisor;
+            divisor *= 10;
+        }
+        self.code.push(opcode);
+        for &p in params {
+            self.param(p);
+        }
+    }
+
+    // jt (5) or jf (6) to a label, which is always an immediate param.
+    fn jump(&mut self, op: i64, cond: Opnd, label: usize) {
+        self.code.push(op + Self::mode(cond) * 100 + 1000);
+        self.param(cond);
+        self.fix(Fix::Label(label));
+    }
+
+    fn copy(&mut self, from: Opnd, to: Opnd) {
+        if from != to {
+            self.emit(1, &[from, Opnd::Imm(0), to]);
+        }
+    }
+
+    fn temp(&mut self) -> Opnd {
+        let t = self.temps;
+        self.temps += 1;
+        self.max_temps = std::cmp::max(self.max_temps, self.temps);
+        if self.in_func {
+            Opnd::Slot(self.locals.len() + t)
+        } else {
+            let name = format!("$t{}", t);
+            match self.globals.iter().position(|g| *g == name) {
+                Some(g) => Opnd::Global(g),
+                None => {
+                    self.globals.push(name);
+                    Opnd::Global(self.globals.len() - 1)
+                }
+            }
+        }
+    }
+
+    fn var(&self, name: &str, line: usize) -> Result<Opnd, String> {
+        if let Some(s) = self.locals.iter().position(|l| l == name) {
+            return Ok(Opnd::Slot(s));
+        }
+        match self.globals.iter().position(|g| g == name) {
+            Some(g) => Ok(Opnd::Global(g)),
+            None => Err(format!("line {}: undefined variable {}", line, name)),
+        }
+    }
+
+    fn binop(&mut self, op: i64, a: Opnd, b: Opnd) -> Opnd {
+        let t = self.temp();
+        self.emit(op, &[a, b, t]);
+        t
+    }
+
+    fn is_zero(&mut self, a: Opnd) -> Opnd {
+        self.binop(8, a, Opnd::Imm(0))
+    }
+
+    fn expr(&mut self, e: &Expr) -> Result<Opnd, String> {
+        Ok(match e {
+            &Expr::Num(n) => Opnd::Imm(n),
+            Expr::Var(name, line) => self.var(name, *line)?,
+            Expr::Input => {
+                let t = self.temp();
+                self.emit(3, &[t]);
+                t
+            }
+            Expr::Mem(addr) => {
+                // Write the address into the next instruction's first param.
+                let addr = self.expr(addr)?;
+                let t = self.temp();
+                let param = self.ahead(4 + 1);
+                self.emit(1, &[addr, Opnd::Imm(0), Opnd::AtLabel(param)]);
+                self.emit(1, &[Opnd::Abs(0), Opnd::Imm(0), t]);
+                t
+            }
+            Expr::Neg(a) => {
+                let a = self.expr(a)?;
+                self.binop(2, a, Opnd::Imm(-1))
+            }
+            Expr::Not(a) => {
+                let a = self.expr(a)?;
+                self.is_zero(a)
+            }
+            Expr::Call(name, args, line) => self.call(name, args, *line)?,
+            Expr::Binary(op, a, b) => {
+                let a = self.expr(a)?;
+                let b = self.expr(b)?;
+                match op {
+                    BinOp::Add => self.binop(1, a, b),
+                    BinOp::Sub => {
+                        let nb = self.binop(2, b, Opnd::Imm(-1));
+                        self.binop(1, a, nb)
+                    }
+                    BinOp::Mul => self.binop(2, a, b),
+                    BinOp::Lt => self.binop(7, a, b),
+                    BinOp::Gt => self.binop(7, b, a),
+                    BinOp::Le => {
+                        let gt = self.binop(7, b, a);
+                        self.is_zero(gt)
+                    }
+                    BinOp::Ge => {
+                        let lt = self.binop(7, a, b);
+                        self.is_zero(lt)
+                    }
+                    BinOp::Eq => self.binop(8, a, b),
+                    BinOp::Ne => {
+                        let eq = self.binop(8, a, b);
+                        self.is_zero(eq)
+                    }
+                    BinOp::And => {
+                        let (za, zb) = (self.is_zero(a), self.is_zero(b));
+                        let either = self.binop(1, za, zb);
+                        self.is_zero(either)
+                    }
+                    BinOp::Or => {
+                        let (za, zb) = (self.is_zero(a), self.is_zero(b));
+                        let both = self.binop(2, za, zb);
+                        self.is_zero(both)
+                    }
+                }
+            }
+        })
+    }
+
+    fn call(&mut self, name: &str, args: &[Expr], line: usize) -> Result<Opnd, String> {
+        let f = *self
+            .funcs
+            .get(name)
+            .ok_or_else(|| format!("line {}: undefined function {}", line, name))?;
+        let want = self.prog.funcs[f].params.len();
+        if args.len() != want {
+            return Err(format!(
+                "line {}: {} takes {} arguments, not {}",
+                line,
+                name,
+                want,
+                args.len()
+            ));
+        }
+        // Evaluated before any are stored, since evaluating one may itself make a call.
+        let mut vals = Vec::with_capacity(args.len());
+        for a in args {
+            vals.push(self.expr(a)?);
+        }
+        for (i, &v) in vals.iter().enumerate() {
+            self.copy(v, Opnd::Out(i as i64 + 1));
+        }
+        let ret = self.ahead(4 + 3);
+        self.emit(1, &[Opnd::Label(ret), Opnd::Imm(0), Opnd::Out(0)]);
+        self.code.extend([1105, 1]);
+        self.fix(Fix::Func(f));
+
+        let t = self.temp();
+        self.copy(Opnd::Out(1), t);
+        Ok(t)
+    }
+
+    fn stmts(&mut self, stmts: &[Stmt]) -> Result<(), String> {
+        for s in stmts {
+            self.temps = 0;
+            self.stmt(s)?;
+        }
+        Ok(())
+    }
+
+    fn stmt(&mut self, s: &Stmt) -> Result<(), String> {
+        match s {
+            Stmt::Var(name, e) => {
+                let v = self.expr(e)?;
+                let to = self.var(name, 0)?;
+                self.copy(v, to);
+            }
+            Stmt::Assign(Expr::Var(name, line), e) => {
+                let v = self.expr(e)?;
+                let to = self.var(name, *line)?;
+                self.copy(v, to);
+            }
+            Stmt::Assign(Expr::Mem(addr), e) => {
+                let addr = self.expr(addr)?;
+                let v = self.expr(e)?;
+                // Write the address into the next instruction's output param.
+                let param = self.ahead(4 + 3);
+                self.emit(1, &[addr, Opnd::Imm(0), Opnd::AtLabel(param)]);
+                self.emit(1, &[v, Opnd::Imm(0), Opnd::Abs(0)]);
+            }
+            Stmt::Assign(..) => unreachable!("parser only allows variables and mem"),
+            Stmt::If(cond, then, otherwise) => {
+                let c = self.expr(cond)?;
+                let (else_l, end_l) = (self.label(), self.label());
+                self.jump(6, c, else_l);
+                self.stmts(then)?;
+                if !otherwise.is_empty() {
+                    self.jump(5, Opnd::Imm(1), end_l);
+                }
+                self.place(else_l);
+                self.stmts(otherwise)?;
+                self.place(end_l);
+            }
+            Stmt::While(cond, body) => {
+                let (top_l, end_l) = (self.label(), self.label());
+                self.place(top_l);
+                let c = self.expr(cond)?;
+                self.jump(6, c, end_l);
+                self.stmts(body)?;
+                self.jump(5, Opnd::Imm(1), top_l);
+                self.place(end_l);
+            }
+            Stmt::Return(value, line) => {
+                if !self.in_func {
+                    return Err(format!("line {}: return outside a function", line));
+                }
+                let v = match value {
+                    Some(e) => self.expr(e)?,
+                    None => Opnd::Imm(0),
+                };
+                self.ret(v);
+            }
+            Stmt::Output(e) => {
+                let v = self.expr(e)?;
+                self.emit(4, &[v]);
+            }
+            Stmt::OutputStr(s) => {
+                for c in s.chars() {
+                    self.emit(4, &[Opnd::Imm(c as i64)]);
+                }
+            }
+            Stmt::Halt => self.code.push(99),
+            Stmt::Expr(e) => {
+                self.expr(e)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn ret(&mut self, v: Opnd) {
+        self.copy(v, Opnd::Slot(1));
+        self.code.push(109);
+        self.fix(Fix::NegFrameSize);
+        self.code.extend([2106, 0, 0]);
+    }
+
+    fn func(&mut self, f: &Func) -> Result<(), String> {
+        let start = self.code.len();
+        let fixes_before = self.fixes.len();
+
+        self.locals = vec!["$ret".to_string()];
+        self.locals.extend(f.params.iter().cloned());
+        if f.params.is_empty() {
+            self.locals.push("$result".to_string());
+        }
+        debug_assert_eq!(self.locals.len(), first_local(f.params.len()));
+        let mut vars = Vec::new();
+        declared(&f.body, &mut vars);
+        for v in vars {
+            if self.locals.contains(&v) {
+                return Err(format!(
+                    "line {}: {} declared twice in {}",
+                    f.line, v, f.name
+                ));
+            }
+            self.locals.push(v);
+        }
+        self.in_func = true;
+        self.max_temps = 0;
+
+        self.code.push(109);
+        self.fix(Fix::FrameSize);
+        self.stmts(&f.body)?;
+        self.ret(Opnd::Imm(0));
+
+        // Now the frame size is known, so frame offsets are too.
+        let size = (self.locals.len() + self.max_temps) as i64;
+        let mut kept = Vec::new();
+        for &(at, fix) in &self.fixes[fixes_before..] {
+            match fix {
+                Fix::Slot(s) => self.code[at] = s as i64 - size,
+                Fix::FrameSize => self.code[at] = size,
+                Fix::NegFrameSize => self.code[at] = -size,
+                _ => kept.push((at, fix)),
+            }
+        }
+        self.fixes.truncate(fixes_before);
+        self.fixes.extend(kept);
+        debug_assert_eq!(self.code[start], 109);
+        Ok(())
+    }
+
+    fn new(prog: &'p Program, globals: Vec<String>) -> Result<Self, String> {
+        let mut funcs = HashMap::new();
+        for (i, f) in prog.funcs.iter().enumerate() {
+            if funcs.insert(f.name.as_str(), i).is_some() {
+                return Err(format!("line {}: {} defined twice", f.line, f.name));
+            }
+        }
+        Ok(Self {
+            prog,
+            code: Vec::new(),
+            fixes: Vec::new(),
+            labels: Vec::new(),
+            globals,
+            funcs,
+            locals: Vec::new(),
+            in_func: false,
+            temps: 0,
+            max_temps: 0,
+        })
+    }
+
+    // Where each function starts.
+    fn funcs(&mut self) -> Result<Vec<usize>, String> {
+        let mut starts = Vec::with_capacity(self.prog.funcs.len());
+        for f in &self.prog.funcs {
+            starts.push(self.code.len());
+            self.func(f)?;
+        }
+        Ok(starts)
+    }
+
+    fn resolve(&mut self, func_starts: &[usize], globals_start: usize, stack: usize) {
+        for &(at, fix) in &self.fixes {
+            self.code[at] = match fix {
+                Fix::Label(l) => self.labels[l].expect("label never placed") as i64,
+                Fix::Func(f) => func_starts[f] as i64,
+                Fix::Global(g) => (globals_start + g) as i64,
+                Fix::Stack => stack as i64,
+                Fix::Slot(_) | Fix::FrameSize | Fix::NegFrameSize => {
+                    unreachable!("frame fixes are resolved per function")
+                }
+            };
+        }
+    }
+}
+
+pub fn compile_program(prog: &Program) -> Result<Vec<i64>, String> {
+    let mut globals = Vec::new();
+    declared(&prog.main, &mut globals);
+    let mut gen = Gen::new(prog, globals)?;
+
+    gen.code.push(109);
+    gen.fix(Fix::Stack);
+    gen.stmts(&prog.main)?;
+    gen.code.push(99);
+    let func_starts = gen.funcs()?;
+
+    let globals_start = gen.code.len();
+    let stack = globals_start + gen.globals.len();
+    gen.resolve(&func_starts, globals_start, stack);
+    gen.code.resize(stack, 0);
+    Ok(gen.code)
+}
+
+// Functions compiled to go anywhere in some other program.
+// Their code is as if placed at 0; relocs are the cells holding addresses in it, which move with it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Relocatable {
+    pub code: Vec<i64>,
+    // Each function's name and where it starts.
+    pub entries: Vec<(String, usize)>,
+    pub relocs: Vec<usize>,
+}
+
+impl Relocatable {
+    // The code as placed at base.
+    pub fn at(&self, base: usize) -> Vec<i64> {
+        let mut code = self.code.clone();
+        for &at in &self.relocs {
+            code[at] += base as i64;
+        }
+        code
+    }
+}
+
+// Just the functions, ignoring any top-level code; they can't use globals, having none.
+pub fn compile_functions(prog: &Program) -> Result<Relocatable, String> {
+    let mut gen = Gen::new(prog, Vec::new())?;
+    let func_starts = gen.funcs()?;
+    gen.resolve(&func_starts, 0, 0);
+    let relocs = gen.fixes.iter().map(|&(at, _)| at).collect();
+    let names = prog.funcs.iter().map(|f| f.name.clone());
+    Ok(Relocatable {
+        code: gen.code,
+        entries: names.zip(func_starts).collect(),
+        relocs,
+    })
+}
+
+// Names of the functions these call.
+fn calls(stmts: &[Stmt], names: &mut Vec<String>) {
+    fn expr(e: &Expr, names: &mut Vec<String>) {
+        match e {
+            Expr::Num(_) | Expr::Var(..) | Expr::Input => {}
+            Expr::Call(name, args, _) => {
+                names.push(name.clone());
+                args.iter().for_each(|a| expr(a, names));
+            }
+            Expr::Mem(a) | Expr::Neg(a) | Expr::Not(a) => expr(a, names),
+            Expr::Binary(_, a, b) => {
+                expr(a, names);
+                expr(b, names);
+            }
+        }
+    }
+    for s in stmts {
+        match s {
+            Stmt::Var(_, e) | Stmt::Output(e) | Stmt::Expr(e) | Stmt::Return(Some(e), _) => {
+                expr(e, names)
+            }
+            Stmt::Assign(a, b) => {
+                expr(a, names);
+                expr(b, names);
+            }
+            Stmt::If(c, a, b) => {
+                expr(c, names);
+                calls(a, names);
+                calls(b, names);
+            }
+            Stmt::While(c, body) => {
+                expr(c, names);
+                calls(body, names);
+            }
+            Stmt::Return(None, _) | Stmt::OutputStr(_) | Stmt::Halt => {}
+        }
+    }
+}
+
+// Adds the functions from lib that prog calls but doesn't define, and any they need in turn.
+pub fn link(prog: &mut Program, lib: &Program) {
+    let mut wanted = Vec::new();
+    calls(&prog.main, &mut wanted);
+    for f in &prog.funcs {
+        calls(&f.body, &mut wanted);
+    }
+    while let Some(name) = wanted.pop() {
+        if prog.funcs.iter().any(|f| f.name == name) {
+            continue;
+        }
+        if let Some(f) = lib.funcs.iter().find(|f| f.name == name) {
+            calls(&f.body, &mut wanted);
+            prog.funcs.push(f.clone());
+        }
+    }
+}
+
+// Parses, links the standard library, and compiles.
+pub fn compile(src: &str) -> Result<Vec<i64>, String> {
+    let mut prog = parse(src)?;
+    link(&mut prog, &super::stdlib::program());
+    compile_program(&prog)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::Computer;
+
+    // Compiles src and runs it on input to a halt.
+    fn outputs(src: &str, input: &[i64], want: &[i64]) {
+        let mem = compile(src).expect("didn't compile");
+        let mut ic = Computer::new(&mem);
+        for &v in input {
+            ic.push_input(v);
+        }
+        ic.cont();
+        assert!(ic.is_halted(), "didn't halt");
+        assert_eq!(ic.output.drain(..).collect::<Vec<_>>(), want);
+    }
+
+    fn prints(src: &str, want: &str) {
+        let want: Vec<_> = want.bytes().map(i64::from).collect();
+        outputs(src, &[], &want);
+    }
+
+    fn fails(src: &str, want: &str) {
+        assert_eq!(compile(src), Err(want.to_string()));
+    }
+
+    crate::tests! {
+        outputs {
+            arithmetic("output 1 + 2 * 3 - -4;", &[], &[11]);
+            precedence("output (1 + 2) * 3; output 1 < 2 == 1; output !0 || 0 && 0;", &[], &[9, 1, 1]);
+            div_positive("output 17 / 5; output 17 % 5;", &[], &[3, 2]);
+            div_negative("output -17 / 5; output -17 % 5; output 17 / -5; output 17 % -5;", &[], &[-3, -2, -3, 2]);
+            inputs("var a = input(); var b = input(); output a * b;", &[6, 7], &[42]);
+            while_loop("var i = 0; while i < 3 { output i; i = i + 1; }", &[], &[0, 1, 2]);
+            if_else("var n = input(); if n < 0 { output 0; } else { output 1; }", &[-5], &[0]);
+            globals("var g = 1; fn bump() { g = g + 1; } bump(); bump(); output g;", &[], &[3]);
+            recursion("fn fact(n) { if n < 2 { return 1; } return n * fact(n - 1); } output fact(10);", &[], &[3628800]);
+            ackermann(
+                "fn ack(m, n) {
+                    if m == 0 { return n + 1; }
+                    if n == 0 { return ack(m - 1, 1); }
+                    return ack(m - 1, ack(m, n - 1));
+                }
+                output ack(2, 3);",
+                &[],
+                &[9]
+            );
+            mem_access("var p = alloc(2); mem[p + 1] = 5; output mem[p + 1];", &[], &[5]);
+            halt("output 1; halt; output 2;", &[], &[1]);
+            read_int("output read_int() + 1;", &[45, 52, 50, 10], &[-41]);
+        }
+        prints {
+            print_negative("print_int(-1203);", "-1203");
+            strings("output \"hi\\n\";", "hi\n");
+        }
+        fails {
+            undefined("output x;", "line 1: undefined variable x");
+            twice("fn f() {}\nfn f() {}", "line 2: f defined twice");
+            return_outside("return 1;", "line 1: return outside a function");
+        }
+    }
+}
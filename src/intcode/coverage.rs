@@ -0,0 +1,148 @@
+// Which cells a program executes, and which it only uses as data.
+
+use super::disasm;
+use super::symbols::Symbols;
+use super::{Computer, Fault};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Use {
+    // Times executed as the start of an instruction.
+    pub exec: u64,
+    // Times fetched as a param of an executed instruction.
+    pub operand: u64,
+    pub read: u64,
+    pub written: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Code,
+    Data,
+    Unreached,
+}
+
+impl Use {
+    pub fn kind(&self) -> Kind {
+        if self.exec > 0 || self.operand > 0 {
+            Kind::Code
+        } else if self.read > 0 || self.written > 0 {
+            Kind::Data
+        } else {
+            Kind::Unreached
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Coverage {
+    pub cells: BTreeMap<usize, Use>,
+    pub steps: u64,
+    // One per run that faulted; a faulting instruction counts as executed.
+    pub faults: Vec<Fault>,
+}
+
+impl Coverage {
+    pub fn get(&self, addr: usize) -> Use {
+        self.cells.get(&addr).cloned().unwrap_or_default()
+    }
+
+    // Call after each step that actually executed.
+    pub fn record(&mut self, ic: &Computer) {
+        let access = ic.last_access();
+        let pos = access.pos;
+        let width = super::width(ic.get(pos)).unwrap_or(1);
+
+        self.steps += 1;
+        self.cells.entry(pos).or_default().exec += 1;
+        for operand in (pos + 1)..(pos + width) {
+            self.cells.entry(operand).or_default().operand += 1;
+        }
+        for &(addr, _) in access.reads.iter().flatten() {
+            self.cells.entry(addr).or_default().read += 1;
+        }
+        if let Some((addr, _)) = access.write {
+            self.cells.entry(addr).or_default().written += 1;
+        }
+    }
+
+    // Runs until halt, fault, running out of inputs, or running out of budget.
+    pub fn run(&mut self, mem: &[i64], inputs: &[i64], budget: u64) {
+        let mut ic = Computer::new(mem);
+        ic.trap_crashes();
+        let mut inputs = inputs.iter();
+        for _ in 0..budget {
+            if ic.is_halted() {
+                break;
+            }
+            if ic.is_blocked() {
+                match inputs.next() {
+                    Some(&v) => ic.push_input(v),
+                    None => break,
+                }
+            }
+            ic.step();
+            if let Some(fault) = ic.fault() {
+                self.cells.entry(fault.pos()).or_default().exec += 1;
+                self.faults.push(fault.clone());
+                break;
+            }
+            if !ic.is_blocked() {
+                self.record(&ic);
+            }
+        }
+    }
+
+    // One line per instruction or data cell, with unreached runs collapsed.
+    pub fn listing(&self, mem: &[i64], syms: &Symbols) -> String {
+        let last_used = self.cells.keys().next_back().map_or(0, |&a| a + 1);
+        let end = std::cmp::max(mem.len(), last_used);
+        let value = |addr: usize| mem.get(addr).cloned().unwrap_or(0);
+
+        let mut out = String::new();
+        let mut addr = 0;
+        while addr < end {
+            for sym in syms.starting_at(addr) {
+                if sym.comment.is_empty() {
+                    writeln!(out, "{}:", sym.name).unwrap();
+                } else {
+                    writeln!(out, "{}:  ; {}", sym.name, sym.comment).unwrap();
+                }
+            }
+            let u = self.get(addr);
+            match u.kind() {
+                Kind::Code if u.exec > 0 => match disasm::decode(mem, addr) {
+                    Some(inst) => {
+                        let inst_s = inst.annotated(syms);
+                        writeln!(out, "{:>6} {:>9}x  {}", addr, u.exec, inst_s).unwrap();
+                        addr = inst.next();
+                    }
+                    None => {
+                        writeln!(out, "{:>6} {:>9}x  ??? {}", addr, u.exec, value(addr)).unwrap();
+                        addr += 1;
+                    }
+                },
+                // Only reached as an operand of an instruction starting elsewhere.
+                Kind::Code => {
+                    writeln!(out, "{:>6} {:>10}  operand {}", addr, "", value(addr)).unwrap();
+                    addr += 1;
+                }
+                Kind::Data => {
+                    let rw = format!("r{} w{}", u.read, u.written);
+                    writeln!(out, "{:>6} {:>10}  data {}", addr, rw, value(addr)).unwrap();
+                    addr += 1;
+                }
+                Kind::Unreached => {
+                    let start = addr;
+                    while addr < end && self.get(addr).kind() == Kind::Unreached {
+                        addr += 1;
+                    }
+                    let n = addr - start;
+                    writeln!(out, "{:>6} {:>10}  unreached ({} cells)", start, "", n).unwrap();
+                }
+            }
+        }
+        out
+    }
+}
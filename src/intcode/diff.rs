@@ -0,0 +1,165 @@
+// Running a program and comparing what came of it with another run,
+// for checking one way of running intcode (or one version of a program) against another.
+// The fuzzer compares interpreter modes; the optimiser, programs before and after rewriting.
+
+use super::Computer;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+#[derive(Clone, Copy)]
+pub struct Mode {
+    pub name: &'static str,
+    pub configure: fn(&mut Computer),
+}
+
+// The first mode is the reference that the others are compared against.
+pub const MODES: &[Mode] = &[
+    Mode {
+        name: "plain",
+        configure: |_| {},
+    },
+    Mode {
+        name: "funopt",
+        configure: |ic| ic.funopt(),
+    },
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Stop {
+    Halted,
+    // Wanted input after all inputs were consumed.
+    Starved,
+    OutOfSteps,
+    Fault(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct Outcome {
+    pub output: Vec<i64>,
+    // Cells whose value differs from the image, sorted by address.
+    pub memory: Vec<(usize, i64)>,
+    pub stop: Stop,
+    pub steps: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Divergence {
+    // None means that side produced no output at that index.
+    Output {
+        index: usize,
+        a: Option<i64>,
+        b: Option<i64>,
+    },
+    // None means that side left the cell as it was in the image.
+    Memory {
+        addr: usize,
+        a: Option<i64>,
+        b: Option<i64>,
+    },
+    Stop {
+        a: Stop,
+        b: Stop,
+    },
+}
+
+impl Divergence {
+    pub fn same_kind(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+pub fn run(mem: &[i64], inputs: &[i64], mode: &Mode, budget: u64) -> Outcome {
+    let mut ic = Computer::new(mem);
+    ic.trap_crashes();
+    (mode.configure)(&mut ic);
+    let mut inputs = inputs.iter();
+    let mut steps = 0;
+
+    let stop = loop {
+        if let Some(fault) = ic.fault() {
+            break Stop::Fault(fault.to_string());
+        }
+        if ic.is_halted() {
+            break Stop::Halted;
+        }
+        if ic.is_blocked() {
+            match inputs.next() {
+                Some(&v) => ic.push_input(v),
+                None => break Stop::Starved,
+            }
+        }
+        if steps >= budget {
+            break Stop::OutOfSteps;
+        }
+        if let Err(e) = catch_unwind(AssertUnwindSafe(|| ic.step())) {
+            break Stop::Fault(panic_message(&*e));
+        }
+        steps += 1;
+    };
+
+    let changed = ic
+        .written()
+        .filter(|&(i, v)| mem.get(i).cloned().unwrap_or(0) != v);
+    let mut memory: Vec<_> = changed.collect();
+    memory.sort_unstable();
+
+    Outcome {
+        output: ic.output.into_inner(),
+        memory,
+        stop,
+        steps,
+    }
+}
+
+fn panic_message(e: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = e.downcast_ref::<String>() {
+        s.clone()
+    } else if let Some(s) = e.downcast_ref::<&str>() {
+        s.to_string()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+pub fn compare(a: &Outcome, b: &Outcome, compare_memory: bool) -> Option<Divergence> {
+    let out_len = std::cmp::max(a.output.len(), b.output.len());
+    for index in 0..out_len {
+        let (oa, ob) = (a.output.get(index).cloned(), b.output.get(index).cloned());
+        if oa != ob {
+            return Some(Divergence::Output {
+                index,
+                a: oa,
+                b: ob,
+            });
+        }
+    }
+
+    if compare_memory {
+        let (mut ia, mut ib) = (a.memory.iter().peekable(), b.memory.iter().peekable());
+        loop {
+            let (addr, va, vb) = match (ia.peek(), ib.peek()) {
+                (None, None) => break,
+                (Some(&&(addr, v)), None) => (addr, Some(v), None),
+                (None, Some(&&(addr, v))) => (addr, None, Some(v)),
+                (Some(&&(aa, va)), Some(&&(ab, vb))) => match aa.cmp(&ab) {
+                    std::cmp::Ordering::Less => (aa, Some(va), None),
+                    std::cmp::Ordering::Greater => (ab, None, Some(vb)),
+                    std::cmp::Ordering::Equal => (aa, Some(va), Some(vb)),
+                },
+            };
+            if va != vb {
+                return Some(Divergence::Memory { addr, a: va, b: vb });
+            }
+            ia.next();
+            ib.next();
+        }
+    }
+
+    if a.stop != b.stop {
+        return Some(Divergence::Stop {
+            a: a.stop.clone(),
+            b: b.stop.clone(),
+        });
+    }
+
+    None
+}
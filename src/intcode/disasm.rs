@@ -0,0 +1,140 @@
+// Decoding instructions for display.
+
+use super::arity;
+use super::symbols::Symbols;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Param {
+    pub mode: i64,
+    pub value: i64,
+}
+
+impl std::fmt::Display for Param {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.mode {
+            0 => write!(f, "[{}]", self.value),
+            1 => write!(f, "{}", self.value),
+            _ if self.value < 0 => write!(f, "[rb-{}]", self.value.unsigned_abs()),
+            _ => write!(f, "[rb+{}]", self.value),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Inst {
+    pub pos: usize,
+    pub opcode: i64,
+    // Input params, then the output param if there is one.
+    pub params: Vec<Param>,
+    pub has_output: bool,
+}
+
+impl Inst {
+    pub fn width(&self) -> usize {
+        1 + self.params.len()
+    }
+
+    pub fn next(&self) -> usize {
+        self.pos + self.width()
+    }
+
+    pub fn inputs(&self) -> &[Param] {
+        let n = self.params.len() - usize::from(self.has_output);
+        &self.params[..n]
+    }
+
+    pub fn output(&self) -> Option<Param> {
+        if self.has_output {
+            self.params.last().cloned()
+        } else {
+            None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.opcode % 100 {
+            1 => "add",
+            2 => "mul",
+            3 => "in",
+            4 => "out",
+            5 => "jt",
+            6 => "jf",
+            7 => "lt",
+            8 => "eq",
+            9 => "arb",
+            _ => "halt",
+        }
+    }
+}
+
+impl Inst {
+    // show gets each param and whether it's a jump target.
+    fn render<F: Fn(&Param, bool) -> String>(&self, show: F) -> String {
+        let is_jump = self.opcode % 100 == 5 || self.opcode % 100 == 6;
+        let mut s = self.name().to_string();
+        for (i, p) in self.inputs().iter().enumerate() {
+            s += if i == 0 { " " } else { ", " };
+            s += &show(p, is_jump && i == 1);
+        }
+        if let Some(p) = self.output() {
+            s += " -> ";
+            s += &show(&p, false);
+        }
+        s
+    }
+
+    // Positional params and constant jump targets are shown by name where there is one.
+    pub fn annotated(&self, syms: &Symbols) -> String {
+        self.render(|p, is_target| {
+            let named = usize::try_from(p.value).ok().and_then(|a| syms.describe(a));
+            match (p.mode, named) {
+                (0, Some(name)) => format!("[{}]", name),
+                (1, Some(name)) if is_target => name,
+                _ => p.to_string(),
+            }
+        })
+    }
+}
+
+impl std::fmt::Display for Inst {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.render(|p, _| p.to_string()))
+    }
+}
+
+// None for anything the interpreter would refuse to run:
+// unknown opcodes, unknown modes, immediate writes, excess mode digits.
+pub fn decode_with<F: Fn(usize) -> i64>(get: F, pos: usize) -> Option<Inst> {
+    let opcode = get(pos);
+    let (num_inputs, has_output) = arity(opcode)?;
+    let num_params = usize::from(num_inputs) + usize::from(has_output);
+
+    let mut divisor = 100;
+    let mut params = Vec::with_capacity(num_params);
+    for i in 0..num_params {
+        let mode = (opcode / divisor) % 10;
+        let is_output = has_output && i == num_params - 1;
+        if mode > 2 || is_output && mode == 1 {
+            return None;
+        }
+        params.push(Param {
+            mode,
+            value: get(pos + 1 + i),
+        });
+        divisor *= 10;
+    }
+    if opcode >= divisor {
+        return None;
+    }
+
+    Some(Inst {
+        pos,
+        opcode,
+        params,
+        has_output,
+    })
+}
+
+pub fn decode(mem: &[i64], pos: usize) -> Option<Inst> {
+    decode_with(|i| mem.get(i).cloned().unwrap_or(0), pos)
+}
@@ -0,0 +1,188 @@
+// Running many Computers as async tasks on one thread, connected by channels.
+//
+// A machine waiting for input is suspended until something sends it some,
+// and each time it outputs it lets the other tasks run, so rings and networks of machines
+// can be written as plain async code instead of loops polling each one in turn.
+// The executor is bundled so that no async runtime is needed.
+
+use super::{Cell, Computer};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+// An unbounded queue; clones share it, so any of them can send or receive.
+pub struct Channel<T> {
+    shared: Rc<RefCell<Queue<T>>>,
+}
+
+struct Queue<T> {
+    values: VecDeque<T>,
+    // Tasks waiting for a value, woken by the next send.
+    waiting: Vec<Waker>,
+}
+
+impl<T> Clone for Channel<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: Rc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> Default for Channel<T> {
+    fn default() -> Self {
+        Self {
+            shared: Rc::new(RefCell::new(Queue {
+                values: VecDeque::new(),
+                waiting: Vec::new(),
+            })),
+        }
+    }
+}
+
+impl<T> Channel<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn send(&self, v: T) {
+        let mut q = self.shared.borrow_mut();
+        q.values.push_back(v);
+        for waker in q.waiting.drain(..) {
+            waker.wake();
+        }
+    }
+
+    // Waits until there's a value, however long that takes.
+    pub async fn recv(&self) -> T {
+        std::future::poll_fn(|cx| {
+            let mut q = self.shared.borrow_mut();
+            match q.values.pop_front() {
+                Some(v) => Poll::Ready(v),
+                None => {
+                    q.waiting.push(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+
+    pub fn try_recv(&self) -> Option<T> {
+        self.shared.borrow_mut().values.pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.borrow().values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Lets every other ready task run before carrying on.
+pub async fn yield_now() {
+    let mut yielded = false;
+    std::future::poll_fn(|cx| {
+        if yielded {
+            Poll::Ready(())
+        } else {
+            yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+// Runs ic until it halts (or faults), taking input from input and sending output to output.
+// Returns it afterwards, to check for a fault or look at its memory.
+pub async fn run<'a, T: Cell>(
+    mut ic: Computer<'a, T>,
+    input: Channel<T>,
+    output: Channel<T>,
+) -> Computer<'a, T> {
+    while !ic.is_halted() {
+        if ic.is_blocked() {
+            ic.push_input(input.recv().await);
+            continue;
+        }
+        ic.step();
+        if !ic.output.is_empty() {
+            for v in ic.output.drain(..) {
+                output.send(v);
+            }
+            yield_now().await;
+        }
+    }
+    ic
+}
+
+type Task<'a> = Pin<Box<dyn Future<Output = ()> + 'a>>;
+
+// Wakers only ever run on the executor's thread, but std's Wake wants them shareable.
+struct TaskWaker {
+    id: usize,
+    ready: Arc<Mutex<VecDeque<usize>>>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.ready.lock().unwrap().push_back(self.id);
+    }
+}
+
+#[derive(Default)]
+pub struct Executor<'a> {
+    // None once finished.
+    tasks: Vec<Option<Task<'a>>>,
+    ready: Arc<Mutex<VecDeque<usize>>>,
+}
+
+impl<'a> Executor<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spawn<F: Future<Output = ()> + 'a>(&mut self, task: F) {
+        self.ready.lock().unwrap().push_back(self.tasks.len());
+        self.tasks.push(Some(Box::pin(task)));
+    }
+
+    // Polls tasks until none can make progress: every one has finished or is waiting for a value nobody has sent.
+    // Send more values and call it again to carry on, as day 23's NAT does when the network goes idle.
+    pub fn run_until_stalled(&mut self) {
+        loop {
+            let id = match self.ready.lock().unwrap().pop_front() {
+                Some(id) => id,
+                None => return,
+            };
+            // Woken more than once, or after finishing.
+            let task = match &mut self.tasks[id] {
+                Some(task) => task,
+                None => continue,
+            };
+            let waker = Waker::from(Arc::new(TaskWaker {
+                id,
+                ready: Arc::clone(&self.ready),
+            }));
+            if task
+                .as_mut()
+                .poll(&mut Context::from_waker(&waker))
+                .is_ready()
+            {
+                self.tasks[id] = None;
+            }
+        }
+    }
+
+    // Tasks not yet finished.
+    pub fn pending(&self) -> usize {
+        self.tasks.iter().filter(|t| t.is_some()).count()
+    }
+}
@@ -0,0 +1,142 @@
+// Exploring the states a program can be driven into, forking a Computer for each input tried.
+//
+// The caller says which inputs to try in each state, and which state the program is in after one
+// (from the state before, the input, and the forked Computer and what it output), or None to go no further.
+// States are deduplicated by key; the first Computer to reach each one is kept.
+
+use super::{Cell, Computer};
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Order {
+    // Every state is first reached by a shortest path.
+    Breadth,
+    Depth,
+}
+
+#[derive(Clone, Debug)]
+pub struct Node<'a, K, T = i64> {
+    pub key: K,
+    pub ic: Computer<'a, T>,
+    // Steps from the start along the first path found.
+    pub depth: usize,
+    // Index of the edge this was first reached by; None for the start.
+    pub via: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Edge<T = i64> {
+    pub from: usize,
+    pub input: Vec<T>,
+    pub output: Vec<T>,
+    pub to: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct Graph<'a, K, T = i64> {
+    pub nodes: Vec<Node<'a, K, T>>,
+    // Every input tried that led to a state, including ones already seen.
+    pub edges: Vec<Edge<T>>,
+    index: HashMap<K, usize>,
+}
+
+impl<'a, K: Eq + Hash, T: Cell> Graph<'a, K, T> {
+    pub fn get(&self, key: &K) -> Option<&Node<'a, K, T>> {
+        self.index.get(key).map(|&i| &self.nodes[i])
+    }
+
+    // The inputs that first reached this state, in order.
+    pub fn path(&self, key: &K) -> Option<Vec<&[T]>> {
+        let mut node = self.get(key)?;
+        let mut path = Vec::new();
+        while let Some(e) = node.via {
+            let edge = &self.edges[e];
+            path.push(&edge.input[..]);
+            node = &self.nodes[edge.from];
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+// start should be waiting for its first input; anything it has already output stays with it.
+pub fn explore<'a, K, T, C, S>(
+    start: Computer<'a, T>,
+    key: K,
+    order: Order,
+    mut candidates: C,
+    mut state: S,
+) -> Graph<'a, K, T>
+where
+    K: Clone + Eq + Hash,
+    T: Cell,
+    C: FnMut(&K, &Computer<'a, T>) -> Vec<Vec<T>>,
+    S: FnMut(&K, &[T], &Computer<'a, T>, &[T]) -> Option<K>,
+{
+    let mut graph = Graph {
+        nodes: vec![Node {
+            key: key.clone(),
+            ic: start,
+            depth: 0,
+            via: None,
+        }],
+        edges: Vec::new(),
+        index: HashMap::new(),
+    };
+    graph.index.insert(key, 0);
+    let mut frontier = VecDeque::from([0]);
+
+    loop {
+        let from = match order {
+            Order::Breadth => frontier.pop_front(),
+            Order::Depth => frontier.pop_back(),
+        };
+        let from = match from {
+            Some(from) => from,
+            None => return graph,
+        };
+        if graph.nodes[from].ic.is_halted() {
+            continue;
+        }
+        let from_key = graph.nodes[from].key.clone();
+
+        for input in candidates(&from_key, &graph.nodes[from].ic) {
+            let mut ic = graph.nodes[from].ic.clone();
+            // Dropping the shared output rather than clearing it, which would copy it first.
+            ic.output = Default::default();
+            for &v in &input {
+                ic.push_input(v);
+            }
+            ic.cont();
+            let output: Vec<_> = ic.output.drain(..).collect();
+            let key = match state(&from_key, &input, &ic, &output) {
+                Some(key) => key,
+                None => continue,
+            };
+
+            let edge = graph.edges.len();
+            let to = match graph.index.get(&key) {
+                Some(&to) => to,
+                None => {
+                    let to = graph.nodes.len();
+                    graph.nodes.push(Node {
+                        key: key.clone(),
+                        ic,
+                        depth: graph.nodes[from].depth + 1,
+                        via: Some(edge),
+                    });
+                    graph.index.insert(key, to);
+                    frontier.push_back(to);
+                    to
+                }
+            };
+            graph.edges.push(Edge {
+                from,
+                input,
+                output,
+                to,
+            });
+        }
+    }
+}
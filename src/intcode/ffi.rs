@@ -0,0 +1,118 @@
+// A C interface to Computer, for embedding the interpreter in tools not written in Rust.
+//
+// Build the library with cargo rustc --release --lib --features ffi --crate-type cdylib;
+// ffi/intcode.h is generated from this file by cbindgen (see ffi/cbindgen.toml), and ffi/test.c exercises it.
+// Computers run in checked mode with crashes trapped, so a bad program is a fault rather than a panic.
+// Functions taking a Computer accept only pointers from intcode_new that haven't been freed.
+
+#![allow(clippy::missing_safety_doc)]
+
+use super::Computer;
+use std::sync::Arc;
+
+// Opaque to C.
+pub struct IntcodeComputer(Computer<'static>);
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntcodeStop {
+    // Only from intcode_step: it can go on.
+    Running,
+    Halted,
+    // Waiting for intcode_push_input.
+    NeedsInput,
+    // Also halted; intcode_pos says where.
+    Faulted,
+}
+
+fn stop(ic: &Computer) -> IntcodeStop {
+    if ic.fault().is_some() {
+        IntcodeStop::Faulted
+    } else if ic.is_halted() {
+        IntcodeStop::Halted
+    } else if ic.is_blocked() {
+        IntcodeStop::NeedsInput
+    } else {
+        IntcodeStop::Running
+    }
+}
+
+// Copies the len values at mem, which needn't outlive the call; free the result with intcode_free.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_new(mem: *const i64, len: usize) -> *mut IntcodeComputer {
+    let mem: Arc<[i64]> = if len == 0 {
+        Arc::from([])
+    } else {
+        Arc::from(std::slice::from_raw_parts(mem, len))
+    };
+    let mut ic = Computer::shared(mem);
+    ic.trap_crashes();
+    ic.checked();
+    Box::into_raw(Box::new(IntcodeComputer(ic)))
+}
+
+// Accepts null.
+#[no_mangle]
+pub extern "C" fn intcode_free(ic: Option<Box<IntcodeComputer>>) {
+    drop(ic);
+}
+
+// Runs one instruction, unless halted or waiting for input.
+#[no_mangle]
+pub extern "C" fn intcode_step(ic: &mut IntcodeComputer) -> IntcodeStop {
+    let ic = &mut ic.0;
+    if !ic.is_halted() && !ic.is_blocked() {
+        ic.step();
+    }
+    stop(ic)
+}
+
+// Runs until halted, faulted, or waiting for input; never returns Running.
+#[no_mangle]
+pub extern "C" fn intcode_run(ic: &mut IntcodeComputer) -> IntcodeStop {
+    ic.0.cont();
+    stop(&ic.0)
+}
+
+#[no_mangle]
+pub extern "C" fn intcode_push_input(ic: &mut IntcodeComputer, v: i64) {
+    ic.0.push_input(v);
+}
+
+#[no_mangle]
+pub extern "C" fn intcode_output_len(ic: &IntcodeComputer) -> usize {
+    ic.0.output.len()
+}
+
+// Moves up to cap of the oldest outputs into buf, returning how many; the rest stay for the next call.
+#[no_mangle]
+pub unsafe extern "C" fn intcode_drain_output(
+    ic: &mut IntcodeComputer,
+    buf: *mut i64,
+    cap: usize,
+) -> usize {
+    let n = std::cmp::min(cap, ic.0.output.len());
+    if n > 0 {
+        let buf = std::slice::from_raw_parts_mut(buf, n);
+        for (b, v) in buf.iter_mut().zip(ic.0.output.drain(..n)) {
+            *b = v;
+        }
+    }
+    n
+}
+
+#[no_mangle]
+pub extern "C" fn intcode_get(ic: &IntcodeComputer, addr: usize) -> i64 {
+    ic.0.get(addr)
+}
+
+#[no_mangle]
+pub extern "C" fn intcode_set(ic: &mut IntcodeComputer, addr: usize, v: i64) {
+    ic.0.set(addr, v);
+}
+
+// The next instruction to run, or where it faulted.
+#[no_mangle]
+pub extern "C" fn intcode_pos(ic: &IntcodeComputer) -> usize {
+    ic.0.pos()
+}
@@ -0,0 +1,252 @@
+// Guessing which puzzle a program is for, from how it starts and how it behaves.
+
+use super::Computer;
+
+// Every input given while probing is this value.
+// 1 is a valid input to most of the puzzles (a diagnostic system ID, a movement, a colour).
+const PROBE: i64 = 1;
+const MAX_INPUTS: usize = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    In,
+    Out(i64),
+}
+
+#[derive(Clone, Debug)]
+pub struct Features {
+    pub prefix: Vec<i64>,
+    pub num_functions: usize,
+    pub events: Vec<Event>,
+    pub halted: bool,
+}
+
+impl Features {
+    pub fn starts_with(&self, prefix: &[i64]) -> bool {
+        self.prefix.starts_with(prefix)
+    }
+
+    pub fn num_inputs(&self) -> usize {
+        self.events.iter().filter(|&&e| e == Event::In).count()
+    }
+
+    // Outputs between each pair of inputs; the first group is before any input.
+    pub fn output_groups(&self) -> Vec<Vec<i64>> {
+        let mut groups = vec![Vec::new()];
+        for e in &self.events {
+            match e {
+                Event::In => groups.push(Vec::new()),
+                Event::Out(v) => groups.last_mut().unwrap().push(*v),
+            }
+        }
+        groups
+    }
+
+    // Output before the first input, if all of it is ASCII.
+    pub fn text_before_input(&self) -> Option<String> {
+        let first = self.output_groups().swap_remove(0);
+        let ascii = first
+            .iter()
+            .map(|&v| u8::try_from(v).ok().filter(u8::is_ascii));
+        let ascii: Option<Vec<u8>> = ascii.collect();
+        ascii
+            .filter(|a| !a.is_empty())
+            .map(|a| String::from_utf8_lossy(&a).into())
+    }
+}
+
+pub fn features(mem: &[i64], budget: u64) -> Features {
+    let mut ic = Computer::new(mem);
+    // A program that isn't intcode at all will likely fault quickly, which ends the probe.
+    ic.trap_crashes();
+    ic.checked();
+    let mut events = Vec::new();
+    let mut steps = 0;
+
+    while !ic.is_halted() && steps < budget {
+        if ic.is_blocked() {
+            if events.iter().filter(|&&e| e == Event::In).count() >= MAX_INPUTS {
+                break;
+            }
+            ic.push_input(PROBE);
+            events.push(Event::In);
+        }
+        ic.step();
+        events.extend(ic.output.drain(..).map(Event::Out));
+        steps += 1;
+    }
+
+    Features {
+        prefix: mem.iter().take(16).cloned().collect(),
+        num_functions: super::functions(mem).len(),
+        events,
+        halted: ic.is_halted() && ic.fault().is_none(),
+    }
+}
+
+pub struct Family {
+    pub name: &'static str,
+    pub day: u8,
+    // Between 0 and 1: about half from how the program starts, half from how it behaves.
+    score: fn(&Features) -> f64,
+}
+
+fn w(weight: f64, cond: bool) -> f64 {
+    if cond {
+        weight
+    } else {
+        0.0
+    }
+}
+
+fn all_in(vs: &[i64], range: std::ops::RangeInclusive<i64>) -> bool {
+    vs.iter().all(|v| range.contains(v))
+}
+
+pub const FAMILIES: &[Family] = &[
+    Family {
+        name: "gravity assist",
+        day: 2,
+        score: |f| {
+            let quiet = f.halted && f.events.is_empty() && f.num_functions == 0;
+            w(0.4, f.starts_with(&[1, 0, 0, 3])) + w(0.6, quiet)
+        },
+    },
+    Family {
+        name: "thermal environment diagnostic",
+        day: 5,
+        score: |f| {
+            let groups = f.output_groups();
+            let diag = f.halted && f.num_inputs() == 1 && groups[1].len() > 1;
+            let zeros = diag && groups[1][..(groups[1].len() - 1)].iter().all(|&v| v == 0);
+            w(0.5, f.starts_with(&[3, 225])) + w(0.5, zeros)
+        },
+    },
+    Family {
+        name: "amplifier",
+        day: 7,
+        score: |f| {
+            let groups = f.output_groups();
+            let amp = f.halted && f.num_inputs() == 2 && groups[2].len() == 1;
+            w(0.5, f.starts_with(&[3, 8, 1001, 8, 10, 8, 105])) + w(0.5, amp)
+        },
+    },
+    Family {
+        name: "BOOST",
+        day: 9,
+        score: |f| {
+            let groups = f.output_groups();
+            let boost = f.halted && f.num_inputs() == 1 && groups[1].len() == 1;
+            w(0.5, f.starts_with(&[1102, 34_463_338, 34_463_338, 63])) + w(0.5, boost)
+        },
+    },
+    Family {
+        name: "hull painting robot",
+        day: 11,
+        score: |f| {
+            let groups = f.output_groups();
+            let pairs = groups.len() > 2
+                && groups[0].is_empty()
+                && groups[1..].iter().all(|g| g.len() == 2 && all_in(g, 0..=1));
+            w(0.5, f.starts_with(&[3, 8, 1005, 8])) + w(0.5, pairs)
+        },
+    },
+    Family {
+        name: "arcade cabinet",
+        day: 13,
+        score: |f| {
+            let outs = f.output_groups().swap_remove(0);
+            let tiles = f.halted && f.num_inputs() == 0 && !outs.is_empty() && outs.len() % 3 == 0;
+            w(0.5, f.starts_with(&[1, 380, 379, 385])) + w(0.5, tiles)
+        },
+    },
+    Family {
+        name: "repair droid",
+        day: 15,
+        score: |f| {
+            let groups = f.output_groups();
+            let statuses = groups.len() > 2
+                && groups[0].is_empty()
+                && groups[1..].iter().all(|g| g.len() == 1 && all_in(g, 0..=2));
+            w(0.5, f.starts_with(&[3, 1033])) + w(0.5, statuses)
+        },
+    },
+    Family {
+        name: "ASCII scaffolding",
+        day: 17,
+        score: |f| {
+            let text = f.text_before_input().unwrap_or_default();
+            let map = text.contains('#') && text.contains('\n');
+            w(0.5, f.starts_with(&[1, 330, 331, 332])) + w(0.5, map)
+        },
+    },
+    Family {
+        name: "tractor beam drone",
+        day: 19,
+        score: |f| {
+            let groups = f.output_groups();
+            let beam = f.halted
+                && f.num_inputs() == 2
+                && groups[2].len() == 1
+                && all_in(&groups[2], 0..=1);
+            w(0.5, f.starts_with(&[109, 424, 203, 1])) + w(0.5, beam)
+        },
+    },
+    Family {
+        name: "springdroid",
+        day: 21,
+        score: |f| {
+            let text = f.text_before_input().unwrap_or_default();
+            w(0.2, f.starts_with(&[109])) + w(0.8, text.contains("Input instructions"))
+        },
+    },
+    Family {
+        name: "network interface controller",
+        day: 23,
+        score: |f| {
+            let groups = f.output_groups();
+            let nic = groups[0].is_empty() && groups.iter().all(|g| g.len() % 3 == 0);
+            w(0.5, f.starts_with(&[3, 62, 1001, 62, 11, 10])) + w(0.5, nic && groups.len() > 2)
+        },
+    },
+    Family {
+        name: "cryostasis droid",
+        day: 25,
+        score: |f| {
+            let text = f.text_before_input().unwrap_or_default();
+            let room = text.contains("==") && text.contains("Command?");
+            w(0.2, f.starts_with(&[109])) + w(0.8, room)
+        },
+    },
+];
+
+// Below this, the best guess isn't worth acting on.
+pub const THRESHOLD: f64 = 0.75;
+
+#[derive(Clone, Copy)]
+pub struct Match {
+    pub family: &'static Family,
+    pub confidence: f64,
+}
+
+// Every family, best match first.
+pub fn identify(mem: &[i64]) -> Vec<Match> {
+    let f = features(mem, 1_000_000);
+    let score = |family: &'static Family| Match {
+        family,
+        confidence: (family.score)(&f),
+    };
+    let mut matches: Vec<_> = FAMILIES.iter().map(score).collect();
+    matches.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    matches
+}
+
+// For a solution to reject a program meant for another day.
+pub fn expect_day(mem: &[i64], day: u8) -> Result<Match, Option<Match>> {
+    let best = identify(mem).into_iter().next();
+    match best {
+        Some(m) if m.confidence >= THRESHOLD && m.family.day == day => Ok(m),
+        Some(m) if m.confidence >= THRESHOLD => Err(Some(m)),
+        _ => Err(None),
+    }
+}
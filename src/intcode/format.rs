@@ -0,0 +1,221 @@
+// Reading and writing programs and memory dumps in formats other than the puzzle's one line.
+//
+// Csv is the puzzle's own format, and Lines is one value per line.
+// Text is for writing by hand: values separated by commas or whitespace, with # comments.
+// Binary is each value as 8 little-endian bytes, for dumps too large to want to parse.
+
+use super::{disasm, lint};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Lines,
+    Text,
+    Binary,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Self::Csv),
+            "lines" => Ok(Self::Lines),
+            "text" => Ok(Self::Text),
+            "binary" => Ok(Self::Binary),
+            _ => Err(format!(
+                "unknown format {}, want csv, lines, text, or binary",
+                s
+            )),
+        }
+    }
+}
+
+impl Format {
+    // Binary for .bin, otherwise the puzzle's format.
+    pub fn for_path(path: &str) -> Self {
+        if path.ends_with(".bin") {
+            Self::Binary
+        } else {
+            Self::Csv
+        }
+    }
+
+    // Binary if it can't be text, which nearly any binary dump can't, since small values have zero bytes.
+    // Text otherwise, which also reads Csv and Lines.
+    pub fn detect(bytes: &[u8]) -> Self {
+        let textual = |c: char| !c.is_control() || c.is_whitespace();
+        match std::str::from_utf8(bytes) {
+            Ok(s) if s.chars().all(textual) => Self::Text,
+            _ => Self::Binary,
+        }
+    }
+}
+
+// Lines and columns count from 1, columns in characters.
+// Binary has no lines, so its errors are on line 1 at the byte offset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub col: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.col, self.message
+        )
+    }
+}
+
+fn error(line: usize, col: usize, message: String) -> ParseError {
+    ParseError {
+        line: line + 1,
+        col: col + 1,
+        message,
+    }
+}
+
+// Parses the word at (line, col), both from 0, col in characters.
+fn value<T: std::str::FromStr>(word: &str, line: usize, col: usize) -> Result<T, ParseError> {
+    word.parse()
+        .map_err(|_| error(line, col, format!("not a number: {}", word)))
+}
+
+pub fn parse_text<T: std::str::FromStr>(s: &str, format: Format) -> Result<Vec<T>, ParseError> {
+    match format {
+        Format::Csv => parse_csv(s),
+        Format::Lines => parse_lines(s),
+        Format::Text => parse_commented(s),
+        Format::Binary => Err(error(0, 0, "binary isn't a text format".to_string())),
+    }
+}
+
+fn parse_csv<T: std::str::FromStr>(s: &str) -> Result<Vec<T>, ParseError> {
+    let s = s.trim_end();
+    if let Some(line) = s.lines().nth(1) {
+        return Err(error(1, 0, format!("more than one line: {}", line)));
+    }
+    let mut col = 0;
+    let mut values = Vec::new();
+    for word in s.split(',') {
+        values.push(value(word, 0, col)?);
+        col += word.chars().count() + 1;
+    }
+    Ok(values)
+}
+
+fn parse_lines<T: std::str::FromStr>(s: &str) -> Result<Vec<T>, ParseError> {
+    let lines = s.strip_suffix('\n').unwrap_or(s).split('\n');
+    let lines = lines.map(|l| l.strip_suffix('\r').unwrap_or(l));
+    lines.enumerate().map(|(i, l)| value(l, i, 0)).collect()
+}
+
+fn parse_commented<T: std::str::FromStr>(s: &str) -> Result<Vec<T>, ParseError> {
+    let mut values = Vec::new();
+    for (i, line) in s.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        // Only a comma directly after a value (give or take whitespace) separates it from the next.
+        let mut after_value = false;
+        let mut word_start = None;
+        let chars = line.char_indices().chain([(line.len(), ' ')]);
+        for (col, (at, c)) in chars.enumerate() {
+            let ends_word = c == ',' || c.is_whitespace();
+            match (word_start, ends_word) {
+                (None, false) => word_start = Some((col, at)),
+                (Some((start_col, start)), true) => {
+                    values.push(value(&line[start..at], i, start_col)?);
+                    word_start = None;
+                    after_value = true;
+                }
+                _ => {}
+            }
+            if c == ',' {
+                if !after_value {
+                    return Err(error(i, col, "comma without a value before it".to_string()));
+                }
+                after_value = false;
+            }
+        }
+    }
+    Ok(values)
+}
+
+pub fn parse_binary(bytes: &[u8]) -> Result<Vec<i64>, ParseError> {
+    let chunks = bytes.chunks_exact(8);
+    if !chunks.remainder().is_empty() {
+        let at = bytes.len() - chunks.remainder().len();
+        let message = format!(
+            "{} bytes left over, not a whole value",
+            chunks.remainder().len()
+        );
+        return Err(error(0, at, message));
+    }
+    let value = |c: &[u8]| i64::from_le_bytes(c.try_into().expect("chunks of 8"));
+    Ok(chunks.map(value).collect())
+}
+
+pub fn parse(bytes: &[u8], format: Format) -> Result<Vec<i64>, ParseError> {
+    if format == Format::Binary {
+        return parse_binary(bytes);
+    }
+    let s = std::str::from_utf8(bytes).map_err(|e| {
+        let before = &bytes[..e.valid_up_to()];
+        let line = before.iter().filter(|&&b| b == b'\n').count();
+        let line_start = before
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |p| p + 1);
+        let col = String::from_utf8_lossy(&before[line_start..])
+            .chars()
+            .count();
+        error(line, col, "not UTF-8".to_string())
+    })?;
+    parse_text(s, format)
+}
+
+// Reads a program or dump in whichever format it seems to be in.
+pub fn load(path: &str) -> Result<Vec<i64>, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+    parse(&bytes, Format::detect(&bytes)).map_err(|e| format!("{}: {}", path, e))
+}
+
+pub fn write(mem: &[i64], format: Format) -> Vec<u8> {
+    let strs = || mem.iter().map(i64::to_string);
+    match format {
+        Format::Csv => (strs().collect::<Vec<_>>().join(",") + "\n").into_bytes(),
+        Format::Lines => strs().map(|s| s + "\n").collect::<String>().into_bytes(),
+        Format::Text => text(mem).into_bytes(),
+        Format::Binary => mem.iter().flat_map(|v| v.to_le_bytes()).collect(),
+    }
+}
+
+// Reachable instructions one to a line, with their disassembly;
+// anything else 8 values to a line. Every line has its address.
+fn text(mem: &[i64]) -> String {
+    let code = lint::reachable(mem);
+    let mut s = String::new();
+    let mut pos = 0;
+    while pos < mem.len() {
+        let inst = Some(pos)
+            .filter(|p| code.contains(p))
+            .and_then(|p| disasm::decode(mem, p))
+            .filter(|i| i.next() <= mem.len());
+        let (end, comment) = match inst {
+            Some(inst) => (inst.next(), format!("{}: {}", pos, inst)),
+            None => {
+                let data = (pos + 1..mem.len())
+                    .take(7)
+                    .take_while(|p| !code.contains(p));
+                (data.last().unwrap_or(pos) + 1, pos.to_string())
+            }
+        };
+        let values: Vec<_> = mem[pos..end].iter().map(i64::to_string).collect();
+        s += &format!("{} # {}\n", values.join(", "), comment);
+        pos = end;
+    }
+    s
+}
@@ -0,0 +1,376 @@
+// Differential fuzzing: generate random well-formed programs,
+// run them under each interpreter mode, and compare what comes out.
+
+use super::diff::{compare, run, Divergence, Mode};
+
+// xorshift64*, so that runs are reproducible from a seed without dependencies.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    // lo inclusive, hi exclusive
+    pub fn range(&mut self, lo: i64, hi: i64) -> i64 {
+        lo + (self.next_u64() % (hi - lo) as u64) as i64
+    }
+
+    pub fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Config {
+    pub budget: u64,
+    pub compare_memory: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Program {
+    pub mem: Vec<i64>,
+    pub inputs: Vec<i64>,
+}
+
+// Runs the program under every mode and compares each against the first.
+// Returns the index of the first mode that disagrees, with how it disagrees.
+pub fn check(prog: &Program, modes: &[Mode], config: Config) -> Option<(usize, Divergence)> {
+    let reference = run(&prog.mem, &prog.inputs, &modes[0], config.budget);
+    modes.iter().enumerate().skip(1).find_map(|(i, mode)| {
+        let outcome = run(&prog.mem, &prog.inputs, mode, config.budget);
+        compare(&reference, &outcome, config.compare_memory).map(|d| (i, d))
+    })
+}
+
+// Address references that can only be resolved once the layout is known.
+enum Fix {
+    Data(usize),
+    Inst(usize),
+    Func(usize),
+}
+
+const GLOBALS: usize = 16;
+const STACK: usize = 64;
+
+struct Asm<'r> {
+    rng: &'r mut Rng,
+    code: Vec<i64>,
+    fixes: Vec<(usize, Fix)>,
+    inst_starts: Vec<usize>,
+    num_funcs: usize,
+}
+
+impl Asm<'_> {
+    fn fix(&mut self, f: Fix) {
+        self.fixes.push((self.code.len(), f));
+        self.code.push(0);
+    }
+
+    // Emits an input param, returning its mode.
+    fn in_param(&mut self, rel: std::ops::Range<i64>) -> i64 {
+        match self.rng.below(3) {
+            0 => {
+                let g = self.rng.below(GLOBALS);
+                self.fix(Fix::Data(g));
+                0
+            }
+            1 => {
+                let v = self.rng.range(-10, 100);
+                self.code.push(v);
+                1
+            }
+            _ => {
+                let v = self.rng.range(rel.start, rel.end);
+                self.code.push(v);
+                2
+            }
+        }
+    }
+
+    fn out_param(&mut self, rel: std::ops::Range<i64>) -> i64 {
+        if self.rng.chance(50) {
+            let g = self.rng.below(GLOBALS);
+            self.fix(Fix::Data(g));
+            0
+        } else {
+            let v = self.rng.range(rel.start, rel.end);
+            self.code.push(v);
+            2
+        }
+    }
+
+    // Emits an instruction whose modes are only known after emitting its params.
+    fn inst(&mut self, op: i64, params: &[bool], rel: std::ops::Range<i64>) {
+        let at = self.code.len();
+        self.code.push(0);
+        let mut opcode = op;
+        let mut divisor = 100;
+        for &is_out in params {
+            let mode = if is_out {
+                self.out_param(rel.clone())
+            } else {
+                self.in_param(rel.clone())
+            };
+            opcode += mode * divisor;
+            divisor *= 10;
+        }
+        self.code[at] = opcode;
+    }
+
+    fn arith(&mut self, rel: std::ops::Range<i64>) {
+        let op = [1, 2, 7, 8][self.rng.below(4)];
+        self.inst(op, &[false, false, true], rel);
+    }
+
+    fn main_inst(&mut self, n: usize) {
+        self.inst_starts.push(self.code.len());
+        let rel = 0..8;
+        match self.rng.below(100) {
+            0..=34 => self.arith(rel),
+            35..=44 => self.inst(3, &[true], rel),
+            45..=59 => self.inst(4, &[false], rel),
+            60..=74 => {
+                let at = self.code.len();
+                self.code.push(0);
+                let mode = self.in_param(rel);
+                self.code[at] = 5 + self.rng.range(0, 2) + mode * 100 + 1000;
+                let target = self.rng.below(n + 1);
+                self.fix(Fix::Inst(target));
+            }
+            75..=79 => {
+                self.code.push(109);
+                self.code.push(self.rng.range(-2, 3));
+            }
+            _ if self.num_funcs == 0 => self.arith(rel),
+            _ => {
+                // arg at rb + 1, return address at rb + 0, then jump.
+                let at = self.code.len();
+                self.code.push(0);
+                let mode = self.in_param(rel);
+                self.code[at] = 21001 + mode * 100;
+                self.code.extend(&[0, 1]);
+                let ret = self.code.len() + 7;
+                self.code.extend(&[21101, ret as i64, 0, 0, 1105, 1]);
+                let f = self.rng.below(self.num_funcs);
+                self.fix(Fix::Func(f));
+                // Keep the result somewhere visible.
+                self.code.extend(&[1201, 1, 0]);
+                let g = self.rng.below(GLOBALS);
+                self.fix(Fix::Data(g));
+            }
+        }
+    }
+
+    fn func(&mut self) {
+        let frame = self.rng.range(2, 6);
+        self.code.extend(&[109, frame]);
+        let rel = (1 - frame)..0;
+        for _ in 0..self.rng.range(1, 6) {
+            // Mostly pure, but sometimes not, so that caching can be caught out.
+            match self.rng.below(10) {
+                0 => self.inst(4, &[false], rel.clone()),
+                1 => {
+                    self.code.extend(&[1, 0, 0]);
+                    let at = self.code.len() - 3;
+                    let g = self.rng.below(GLOBALS);
+                    self.fixes.push((at + 1, Fix::Data(g)));
+                    self.fixes.push((at + 2, Fix::Data(g)));
+                    let g = self.rng.below(GLOBALS);
+                    self.fix(Fix::Data(g));
+                }
+                _ => self.arith(rel.clone()),
+            }
+        }
+        self.code.extend(&[109, -frame, 2106, 0, 0]);
+    }
+}
+
+pub fn generate(rng: &mut Rng, size: usize) -> Program {
+    let num_funcs = rng.below(4);
+    let mut asm = Asm {
+        rng,
+        code: Vec::new(),
+        fixes: Vec::new(),
+        inst_starts: Vec::new(),
+        num_funcs,
+    };
+
+    // rb starts at the stack.
+    asm.code.push(109);
+    asm.fix(Fix::Data(GLOBALS));
+
+    for _ in 0..size {
+        asm.main_inst(size);
+    }
+    asm.inst_starts.push(asm.code.len());
+    asm.code.push(99);
+
+    let mut func_starts = Vec::new();
+    for _ in 0..num_funcs {
+        func_starts.push(asm.code.len());
+        asm.func();
+    }
+
+    let data_base = asm.code.len();
+    let Asm {
+        rng,
+        mut code,
+        fixes,
+        inst_starts,
+        ..
+    } = asm;
+    for (at, fix) in fixes {
+        code[at] = match fix {
+            Fix::Data(i) => data_base + i,
+            Fix::Inst(i) => inst_starts[i],
+            Fix::Func(i) => func_starts[i],
+        } as i64;
+    }
+    code.extend((0..GLOBALS).map(|_| rng.range(-5, 20)));
+    code.extend(std::iter::repeat_n(0, STACK));
+
+    let inputs = (0..rng.below(8)).map(|_| rng.range(-5, 20));
+    Program {
+        mem: code,
+        inputs: inputs.collect(),
+    }
+}
+
+// No-ops of each width, so that addresses don't move when shrinking.
+fn nop(width: usize) -> Option<&'static [i64]> {
+    match width {
+        2 => Some(&[109, 0]),
+        3 => Some(&[1105, 0, 0]),
+        4 => Some(&[109, 0, 109, 0]),
+        _ => None,
+    }
+}
+
+// Removes the instruction at pos, pulling back any position or immediate operand
+// that pointed past it. Relative operands are left alone.
+// This is only a guess at what is an address, but the shrinker rechecks every candidate.
+fn without(mem: &[i64], pos: usize, w: usize) -> Vec<i64> {
+    let mut out = Vec::with_capacity(mem.len() - w);
+    let mut i = 0;
+    while i < mem.len() {
+        let op = mem[i];
+        let iw = super::width(op).unwrap_or(1);
+        if i == pos {
+            i += iw;
+            continue;
+        }
+        out.push(op);
+        let mut divisor = 100;
+        for &v in mem.iter().take(i + iw).skip(i + 1) {
+            let mode = (op / divisor) % 10;
+            let past = usize::try_from(v).is_ok_and(|v| v >= pos + w);
+            out.push(if mode != 2 && past { v - w as i64 } else { v });
+            divisor *= 10;
+        }
+        i += iw;
+    }
+    out
+}
+
+// Greedily simplifies the program while still_fails holds:
+// drops inputs, deletes instructions or replaces them with no-ops,
+// zeroes cells, truncates.
+pub fn shrink<F>(prog: &Program, still_fails: F) -> Program
+where
+    F: Fn(&Program) -> bool,
+{
+    let mut best = prog.clone();
+    let try_candidate = |best: &mut Program, cand: Program| {
+        if cand != *best && still_fails(&cand) {
+            *best = cand;
+            true
+        } else {
+            false
+        }
+    };
+
+    let mut progress = true;
+    while progress {
+        progress = false;
+
+        let mut i = 0;
+        while i < best.inputs.len() {
+            let mut cand = best.clone();
+            cand.inputs.remove(i);
+            if try_candidate(&mut best, cand) {
+                progress = true;
+                continue;
+            }
+            let mut cand = best.clone();
+            cand.inputs[i] = 0;
+            progress |= try_candidate(&mut best, cand);
+            i += 1;
+        }
+
+        let mut chop = best.mem.len() / 2;
+        while chop > 0 {
+            let mut cand = best.clone();
+            cand.mem.truncate(best.mem.len() - chop);
+            if !try_candidate(&mut best, cand) {
+                chop /= 2;
+            } else {
+                progress = true;
+            }
+        }
+
+        let mut pos = 0;
+        while pos < best.mem.len() {
+            let w = match super::width(best.mem[pos]) {
+                Some(w) => w,
+                None => {
+                    if best.mem[pos] != 0 {
+                        let mut cand = best.clone();
+                        cand.mem[pos] = 0;
+                        progress |= try_candidate(&mut best, cand);
+                    }
+                    pos += 1;
+                    continue;
+                }
+            };
+            if pos + w <= best.mem.len() {
+                let cand = Program {
+                    mem: without(&best.mem, pos, w),
+                    inputs: best.inputs.clone(),
+                };
+                if try_candidate(&mut best, cand) {
+                    progress = true;
+                    continue;
+                }
+            }
+            if let Some(nop) = nop(w).filter(|_| pos + w <= best.mem.len()) {
+                if best.mem[pos..(pos + w)] != *nop {
+                    let mut cand = best.clone();
+                    cand.mem[pos..(pos + w)].copy_from_slice(nop);
+                    progress |= try_candidate(&mut best, cand);
+                }
+            }
+            for operand in (pos + 1)..std::cmp::min(pos + w, best.mem.len()) {
+                if best.mem[operand] != 0 {
+                    let mut cand = best.clone();
+                    cand.mem[operand] = 0;
+                    progress |= try_candidate(&mut best, cand);
+                }
+            }
+            pos += w;
+        }
+    }
+
+    best
+}
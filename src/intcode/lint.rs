@@ -0,0 +1,139 @@
+// Checking a program before running it, for what would otherwise fault whenever it's first reached.
+//
+// Only code reachable from 0 is checked: following fall-through, constant jump targets,
+// and the return address of each call (stored just before jumping, as funopt recognises).
+// Computed jumps can't be followed, so code only reached through them isn't checked.
+
+use super::{arity, disasm};
+use std::collections::BTreeSet;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Problem {
+    UnknownOpcode(i64),
+    // A mode digit other than 0, 1, or 2, or more mode digits than params.
+    BadModes(i64),
+    ImmediateWrite(i64),
+    JumpOutOfRange(i64),
+    // A position-mode param that's negative.
+    NegativeAddress(i64),
+    // The instruction runs past the end of the image, or execution reaches the end.
+    FallsOffEnd,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Finding {
+    pub pos: usize,
+    pub problem: Problem,
+}
+
+impl std::fmt::Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}: ", self.pos)?;
+        match self.problem {
+            Problem::UnknownOpcode(op) => write!(f, "unknown opcode {}", op),
+            Problem::BadModes(op) => write!(f, "invalid modes in {}", op),
+            Problem::ImmediateWrite(op) => write!(f, "immediate-mode output param in {}", op),
+            Problem::JumpOutOfRange(t) => write!(f, "jump to {}, outside the program", t),
+            Problem::NegativeAddress(a) => write!(f, "negative address {}", a),
+            Problem::FallsOffEnd => write!(f, "runs off the end of the program"),
+        }
+    }
+}
+
+// Why the instruction at pos can't run, if it can't.
+fn invalid(mem: &[i64], pos: usize) -> Option<Problem> {
+    let opcode = mem[pos];
+    let (num_inputs, has_output) = match arity(opcode) {
+        Some(a) => a,
+        None => return Some(Problem::UnknownOpcode(opcode)),
+    };
+    let num_params = u32::from(num_inputs + u8::from(has_output));
+    let mode = |i: u32| (opcode / 10i64.pow(i + 2)) % 10;
+    if opcode >= 10i64.pow(num_params + 2) || (0..num_params).any(|i| mode(i) > 2) {
+        return Some(Problem::BadModes(opcode));
+    }
+    if has_output && mode(num_params - 1) == 1 {
+        return Some(Problem::ImmediateWrite(opcode));
+    }
+    None
+}
+
+// Instruction starts reachable from 0, and problems found on the way.
+fn walk(mem: &[i64]) -> (BTreeSet<usize>, Vec<Finding>) {
+    let mut seen = BTreeSet::new();
+    let mut findings = Vec::new();
+    let mut todo = vec![0];
+
+    while let Some(pos) = todo.pop() {
+        if !seen.insert(pos) {
+            continue;
+        }
+        let mut found = |problem| findings.push(Finding { pos, problem });
+        if pos >= mem.len() {
+            found(Problem::FallsOffEnd);
+            continue;
+        }
+        if let Some(problem) = invalid(mem, pos) {
+            found(problem);
+            continue;
+        }
+        let inst = disasm::decode(mem, pos).expect("checked valid");
+        if inst.next() > mem.len() {
+            found(Problem::FallsOffEnd);
+            continue;
+        }
+
+        for p in &inst.params {
+            if p.mode == 0 && p.value < 0 {
+                found(Problem::NegativeAddress(p.value));
+            }
+        }
+
+        let ins = inst.inputs();
+        let mut falls_through = true;
+        match inst.opcode % 100 {
+            99 => falls_through = false,
+            5 | 6 => {
+                let jumps_if = inst.opcode % 100 == 5;
+                let cond = Some(ins[0]).filter(|c| c.mode == 1).map(|c| c.value != 0);
+                if cond == Some(!jumps_if) {
+                    // Never jumps.
+                } else {
+                    falls_through = cond.is_none();
+                    if ins[1].mode == 1 {
+                        match usize::try_from(ins[1].value) {
+                            Ok(t) if t < mem.len() => todo.push(t),
+                            _ => found(Problem::JumpOutOfRange(ins[1].value)),
+                        }
+                    }
+                }
+            }
+            1 | 2 if ins.iter().all(|p| p.mode == 1) => {
+                // A call's return address: stored, then a 3-wide jump, then the address.
+                let v = if inst.opcode % 100 == 1 {
+                    ins[0].value.checked_add(ins[1].value)
+                } else {
+                    ins[0].value.checked_mul(ins[1].value)
+                };
+                if v == Some((inst.next() + 3) as i64) {
+                    todo.push(inst.next() + 3);
+                }
+            }
+            _ => {}
+        }
+        if falls_through {
+            todo.push(inst.next());
+        }
+    }
+
+    findings.sort_by_key(|f| f.pos);
+    (seen, findings)
+}
+
+pub fn lint(mem: &[i64]) -> Vec<Finding> {
+    walk(mem).1
+}
+
+pub fn reachable(mem: &[i64]) -> BTreeSet<usize> {
+    walk(mem).0
+}
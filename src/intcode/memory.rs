@@ -0,0 +1,253 @@
+// The cells a Computer has written, shared between clones until one of them writes.
+//
+// A radix tree of pages behind Arcs: cloning copies one pointer,
+// and a write copies only the pages on the path to the cell, and only if they're shared.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+const BITS: u32 = 5;
+const FANOUT: usize = 1 << BITS;
+const MASK: usize = FANOUT - 1;
+
+#[derive(Clone, Debug)]
+enum Node<T> {
+    Empty,
+    // None for cells never written.
+    Leaf(Arc<[Option<T>; FANOUT]>),
+    Branch(Arc<[Node<T>; FANOUT]>),
+}
+
+#[derive(Clone, Debug)]
+pub struct Memory<T> {
+    root: Node<T>,
+    // Levels of branches above the leaves.
+    height: u32,
+    len: usize,
+}
+
+impl<T> Default for Memory<T> {
+    fn default() -> Self {
+        Self {
+            root: Node::Empty,
+            height: 0,
+            len: 0,
+        }
+    }
+}
+
+impl<T: Copy> Memory<T> {
+    fn capacity_bits(&self) -> u32 {
+        BITS * (self.height + 1)
+    }
+
+    fn fits(&self, i: usize) -> bool {
+        self.capacity_bits() >= usize::BITS || i >> self.capacity_bits() == 0
+    }
+
+    pub fn get(&self, i: usize) -> Option<T> {
+        if !self.fits(i) {
+            return None;
+        }
+        let mut node = &self.root;
+        let mut level = self.height;
+        loop {
+            match node {
+                Node::Empty => return None,
+                Node::Leaf(cells) => return cells[i & MASK],
+                Node::Branch(kids) => {
+                    node = &kids[(i >> (BITS * level)) & MASK];
+                    level -= 1;
+                }
+            }
+        }
+    }
+
+    // Returns what was there before, if anything.
+    pub fn insert(&mut self, i: usize, v: T) -> Option<T> {
+        while !self.fits(i) {
+            if !matches!(self.root, Node::Empty) {
+                let old = core::mem::replace(&mut self.root, Node::Empty);
+                let mut kids: [Node<T>; FANOUT] = core::array::from_fn(|_| Node::Empty);
+                kids[0] = old;
+                self.root = Node::Branch(Arc::new(kids));
+            }
+            self.height += 1;
+        }
+        let old = Self::set(&mut self.root, self.height, i, v);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    fn set(node: &mut Node<T>, level: u32, i: usize, v: T) -> Option<T> {
+        if let Node::Empty = node {
+            *node = if level == 0 {
+                Node::Leaf(Arc::new([None; FANOUT]))
+            } else {
+                Node::Branch(Arc::new(core::array::from_fn(|_| Node::Empty)))
+            };
+        }
+        match node {
+            Node::Empty => unreachable!("just filled in"),
+            Node::Leaf(cells) => Arc::make_mut(cells)[i & MASK].replace(v),
+            Node::Branch(kids) => {
+                let kid = &mut Arc::make_mut(kids)[(i >> (BITS * level)) & MASK];
+                Self::set(kid, level - 1, i, v)
+            }
+        }
+    }
+
+    // Cells written.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // In address order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        let mut iter = Iter {
+            branches: Vec::new(),
+            leaf: None,
+        };
+        iter.enter(&self.root, self.height, 0);
+        iter
+    }
+
+    // The highest address written, without visiting the rest.
+    pub fn max_index(&self) -> Option<usize> {
+        let mut node = &self.root;
+        let mut level = self.height;
+        let mut base = 0;
+        loop {
+            // Nothing is ever removed, so every node but Empty has a cell written under it.
+            match node {
+                Node::Empty => return None,
+                Node::Leaf(cells) => {
+                    return cells.iter().rposition(Option::is_some).map(|j| base + j)
+                }
+                Node::Branch(kids) => {
+                    let j = kids.iter().rposition(|k| !matches!(k, Node::Empty))?;
+                    base += j << (BITS * level);
+                    node = &kids[j];
+                    level -= 1;
+                }
+            }
+        }
+    }
+}
+
+pub struct Iter<'m, T> {
+    // Branches on the way down to the current leaf, with the level, first address, and next kid of each.
+    branches: Vec<(&'m [Node<T>; FANOUT], u32, usize, usize)>,
+    // The leaf being walked, with its first address and next cell.
+    leaf: Option<(&'m [Option<T>; FANOUT], usize, usize)>,
+}
+
+impl<'m, T> Iter<'m, T> {
+    fn enter(&mut self, node: &'m Node<T>, level: u32, base: usize) {
+        match node {
+            Node::Empty => {}
+            Node::Leaf(cells) => self.leaf = Some((cells, base, 0)),
+            Node::Branch(kids) => self.branches.push((kids, level, base, 0)),
+        }
+    }
+}
+
+impl<T: Copy> Iterator for Iter<'_, T> {
+    type Item = (usize, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((cells, base, j)) = &mut self.leaf {
+                while *j < FANOUT {
+                    let k = *j;
+                    *j += 1;
+                    if let Some(v) = cells[k] {
+                        return Some((*base + k, v));
+                    }
+                }
+                self.leaf = None;
+            }
+            let (kids, level, base, j) = self.branches.last_mut()?;
+            if *j == FANOUT {
+                self.branches.pop();
+                continue;
+            }
+            let (kid, level, base) = (&kids[*j], *level - 1, *base + (*j << (BITS * *level)));
+            *j += 1;
+            self.enter(kid, level, base);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::collections::BTreeMap;
+
+    // Writes each (address, value) in turn, then compares everything against a BTreeMap given the same.
+    fn matches_btree(writes: &[(usize, i64)]) {
+        let mut mem = Memory::default();
+        let mut want = BTreeMap::new();
+        for &(i, v) in writes {
+            assert_eq!(mem.insert(i, v), want.insert(i, v), "insert {}", i);
+        }
+        for &(i, _) in writes {
+            for i in [i.wrapping_sub(1), i, i.wrapping_add(1)] {
+                assert_eq!(mem.get(i), want.get(&i).cloned(), "get {}", i);
+            }
+        }
+        assert_eq!(mem.len(), want.len());
+        assert_eq!(mem.is_empty(), want.is_empty());
+        assert_eq!(
+            mem.iter().collect::<Vec<_>>(),
+            want.clone().into_iter().collect::<Vec<_>>()
+        );
+        assert_eq!(mem.max_index(), want.keys().next_back().cloned());
+    }
+
+    // Writes to a clone leave the original as it was, and the other way around.
+    fn clones_independent(writes: &[(usize, i64)]) {
+        let mut mem = Memory::default();
+        for &(i, v) in writes {
+            mem.insert(i, v);
+        }
+        let before: Vec<_> = mem.iter().collect();
+        let mut copy = mem.clone();
+        for &(i, v) in writes {
+            copy.insert(i, v + 1);
+        }
+        assert_eq!(mem.iter().collect::<Vec<_>>(), before);
+
+        let copied: Vec<_> = copy.iter().collect();
+        for &(i, _) in writes {
+            mem.insert(i, 0);
+        }
+        mem.insert(1 << 30, 0);
+        assert_eq!(copy.iter().collect::<Vec<_>>(), copied);
+    }
+
+    crate::tests! {
+        matches_btree {
+            empty(&[]);
+            zero(&[(0, 5)]);
+            one_page(&[(3, 1), (0, 2), (31, 3)]);
+            page_boundary(&[(31, 1), (32, 2), (33, 3)]);
+            second_level_boundary(&[(1023, 1), (1024, 2), (1025, 3)]);
+            overwrite(&[(7, 1), (7, 2), (40, 3), (7, 4)]);
+            grow_from_low(&[(0, 1), (100, 2), (100_000, 3), (1 << 40, 4)]);
+            grow_from_high(&[(1 << 40, 4), (100_000, 3), (100, 2), (0, 1)]);
+            top_address(&[(usize::MAX, 1), (0, 2), (usize::MAX - 1, 3)]);
+            sparse(&[(5, 1), (5000, 2), (50, 3), (500_000, 4), (500, 5)]);
+        }
+        clones_independent {
+            clone_one_page(&[(1, 1), (2, 2)]);
+            clone_many_pages(&[(1, 1), (40, 2), (2000, 3), (70_000, 4)]);
+        }
+    }
+}
@@ -0,0 +1,331 @@
+// Rewriting a program into one that does the same in fewer steps, without moving anything.
+//
+// Every instruction stays where it was, so addresses used as data and computed jumps still work.
+// Only code reachable from the start (as lint finds it) is rewritten, and none that's also read as data.
+// A program that writes into its own reachable code is refused outright,
+// as is one using relative mode that doesn't keep its stack clear of its code the way the calling convention does:
+// setting the relative base past the code first, and only lowering it again just before returning.
+
+use super::diff::{self, Divergence, Stop};
+use super::disasm::{self, Inst, Param};
+use super::lint;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// Why a program can't safely be rewritten; pos is the instruction responsible.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Refusal {
+    // Writes to a cell of code.
+    SelfModifying { pos: usize, addr: usize },
+    // Uses relative mode with a stack that might overlap the code.
+    Stack { pos: usize },
+}
+
+impl std::fmt::Display for Refusal {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::SelfModifying { pos, addr } => {
+                write!(f, "{} writes to {}, which is code", pos, addr)?
+            }
+            Self::Stack { pos } => write!(
+                f,
+                "{} uses relative mode, but the stack may overlap the code",
+                pos
+            )?,
+        }
+        write!(f, "; not optimising")
+    }
+}
+
+enum Rewrite {
+    Same,
+    // Does nothing but go on to the next instruction.
+    Nop,
+    Cells(Vec<i64>),
+}
+
+// Reading the param can't fault, so leaving the read out changes nothing.
+fn harmless(p: &Param) -> bool {
+    p.mode == 1 || p.mode == 0 && p.value >= 0
+}
+
+fn opcode(op: i64, modes: &[i64]) -> i64 {
+    let digits = modes.iter().rev().fold(0, |acc, m| acc * 10 + m);
+    digits * 100 + op
+}
+
+fn rewrite(inst: &Inst) -> Rewrite {
+    let ins = inst.inputs();
+    match inst.opcode % 100 {
+        op @ (1 | 2 | 7 | 8) => {
+            let out = inst.output().expect("arithmetic has an output");
+            let set = |src: Param| {
+                let op = opcode(1, &[src.mode, 1, out.mode]);
+                Rewrite::Cells(vec![op, src.value, 0, out.value])
+            };
+            let (a, b) = (ins[0], ins[1]);
+            if a.mode == 1 && b.mode == 1 {
+                let v = match op {
+                    1 => a.value.checked_add(b.value),
+                    2 => a.value.checked_mul(b.value),
+                    7 => Some(i64::from(a.value < b.value)),
+                    _ => Some(i64::from(a.value == b.value)),
+                };
+                return v.map_or(Rewrite::Same, |v| set(Param { mode: 1, value: v }));
+            }
+            // A move is an add of 0 or a multiply by 1, either way round.
+            let identity = if op == 1 { 0 } else { 1 };
+            let src = match (a, b) {
+                (src, Param { mode: 1, value }) if value == identity && op <= 2 => src,
+                (Param { mode: 1, value }, src) if value == identity && op <= 2 => src,
+                _ => return Rewrite::Same,
+            };
+            if src == out && harmless(&src) {
+                Rewrite::Nop
+            } else if src != a || b.value != 0 || op != 1 {
+                set(src)
+            } else {
+                Rewrite::Same
+            }
+        }
+        op @ (5 | 6) => {
+            let (cond, target) = (ins[0], ins[1]);
+            let to_next = target.mode == 1 && target.value == inst.next() as i64;
+            if cond.mode != 1 {
+                return if to_next && harmless(&cond) {
+                    Rewrite::Nop
+                } else {
+                    Rewrite::Same
+                };
+            }
+            if (cond.value != 0) != (op == 5) || to_next {
+                Rewrite::Nop
+            } else {
+                Rewrite::Cells(vec![opcode(5, &[1, target.mode]), 1, target.value])
+            }
+        }
+        9 if ins[0].mode == 1 && ins[0].value == 0 => Rewrite::Nop,
+        _ => Rewrite::Same,
+    }
+}
+
+// A return: a jump to the address at [rb+0].
+fn returns(inst: &Inst) -> bool {
+    matches!(inst.opcode % 100, 5 | 6) && inst.inputs()[1] == Param { mode: 2, value: 0 }
+}
+
+// Whether relative-mode accesses can reach the code, assuming calls and returns pair up.
+fn check_stack(mem: &[i64], insts: &BTreeMap<usize, Inst>) -> Result<(), Refusal> {
+    let relative = |i: &&Inst| i.params.iter().any(|p| p.mode == 2);
+    let first = match insts.values().find(relative) {
+        Some(inst) => inst.pos,
+        None => return Ok(()),
+    };
+    let base = match insts.get(&0) {
+        Some(inst) if inst.opcode == 109 && mem[1] > 0 => mem[1],
+        _ => return Err(Refusal::Stack { pos: first }),
+    };
+    for inst in insts.values().filter(|i| i.pos != 0 && i.opcode % 100 == 9) {
+        let arb = inst.inputs()[0];
+        let before_return = || insts.get(&inst.next()).is_some_and(returns);
+        if arb.mode != 1 || arb.value < 0 && !before_return() {
+            return Err(Refusal::Stack { pos: inst.pos });
+        }
+    }
+    let params = insts
+        .values()
+        .flat_map(|i| i.params.iter().map(move |p| (i.pos, p)));
+    let end = insts.values().map(Inst::next).max().unwrap_or(0) as i64;
+    for (pos, p) in params.filter(|(_, p)| p.mode == 2) {
+        if base.checked_add(p.value).is_none_or(|a| a < end) {
+            return Err(Refusal::Stack { pos });
+        }
+    }
+    Ok(())
+}
+
+// Where an immediate jump lands, if it always jumps.
+fn always_jumps_to(inst: &Inst) -> Option<usize> {
+    let op = inst.opcode % 100;
+    if op != 5 && op != 6 {
+        return None;
+    }
+    let (cond, target) = (inst.inputs()[0], inst.inputs()[1]);
+    let taken = cond.mode == 1 && (cond.value != 0) == (op == 5);
+    if taken && target.mode == 1 {
+        usize::try_from(target.value).ok()
+    } else {
+        None
+    }
+}
+
+// Folds constant arithmetic, turns multiplies by 1 and adds of 0 into plain moves,
+// removes no-ops (moves to the same place, jumps that never go anywhere else, relative base adjustments of 0)
+// by jumping over them, and makes jumps to jumps or no-ops go straight to where they'd end up.
+pub fn optimise(mem: &[i64]) -> Result<Vec<i64>, Refusal> {
+    let insts: BTreeMap<usize, Inst> = lint::reachable(mem)
+        .into_iter()
+        .filter_map(|pos| disasm::decode(mem, pos).filter(|i| i.next() <= mem.len()))
+        .map(|inst| (inst.pos, inst))
+        .collect();
+    check_stack(mem, &insts)?;
+
+    // Instructions that must stay as they are: those read as data, or overlapping another.
+    let mut fixed = HashSet::new();
+    let mut owner = HashMap::new();
+    for inst in insts.values() {
+        for addr in inst.pos..inst.next() {
+            if let Some(other) = owner.insert(addr, inst.pos) {
+                fixed.insert(other);
+                fixed.insert(inst.pos);
+            }
+        }
+    }
+    for inst in insts.values() {
+        for (i, p) in inst.params.iter().enumerate() {
+            let addr = match usize::try_from(p.value) {
+                Ok(addr) if p.mode == 0 => addr,
+                _ => continue,
+            };
+            if let Some(&code) = owner.get(&addr) {
+                if inst.has_output && i == inst.params.len() - 1 {
+                    return Err(Refusal::SelfModifying {
+                        pos: inst.pos,
+                        addr,
+                    });
+                }
+                fixed.insert(code);
+            }
+        }
+    }
+
+    let mut out = mem.to_vec();
+    let mut nops = HashSet::new();
+    for inst in insts.values().filter(|i| !fixed.contains(&i.pos)) {
+        match rewrite(inst) {
+            Rewrite::Same => {}
+            Rewrite::Nop => {
+                nops.insert(inst.pos);
+            }
+            Rewrite::Cells(cells) => out[inst.pos..inst.next()].copy_from_slice(&cells),
+        }
+    }
+
+    // Where execution arriving at pos next does something, passing over no-ops and jumps.
+    let decoded = out.clone();
+    let lands = |mut pos: usize| {
+        let mut seen = HashSet::new();
+        while seen.insert(pos) && insts.contains_key(&pos) && !fixed.contains(&pos) {
+            let inst = disasm::decode(&decoded, pos).expect("rewrites stay valid");
+            pos = if nops.contains(&pos) {
+                inst.next()
+            } else {
+                match always_jumps_to(&inst) {
+                    Some(target) => target,
+                    None => break,
+                }
+            };
+        }
+        pos
+    };
+
+    for &pos in insts.keys().filter(|p| !fixed.contains(p)) {
+        let inst = disasm::decode(&decoded, pos).expect("rewrites stay valid");
+        if nops.contains(&pos) {
+            // Two-wide no-ops have no room for a jump, so they stay.
+            if inst.width() >= 3 {
+                out[pos..pos + 3].copy_from_slice(&[1105, 1, lands(inst.next()) as i64]);
+            }
+        } else if matches!(inst.opcode % 100, 5 | 6) && inst.inputs()[1].mode == 1 {
+            if let Ok(target) = usize::try_from(inst.inputs()[1].value) {
+                out[pos + 2] = lands(target) as i64;
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+// Runs both programs on the same inputs with the same budget,
+// returning how many steps each took, or how they differ.
+// If either runs out of steps, only the output both got as far as is compared.
+pub fn check(
+    original: &[i64],
+    optimised: &[i64],
+    inputs: &[i64],
+    budget: u64,
+) -> Result<(u64, u64), Divergence> {
+    let plain = &diff::MODES[0];
+    let mut a = diff::run(original, inputs, plain, budget);
+    let mut b = diff::run(optimised, inputs, plain, budget);
+    if a.stop == Stop::OutOfSteps || b.stop == Stop::OutOfSteps {
+        let len = std::cmp::min(a.output.len(), b.output.len());
+        a.output.truncate(len);
+        b.output.truncate(len);
+        b.stop = a.stop.clone();
+        b.memory = a.memory.clone();
+    }
+    match diff::compare(&a, &b, true) {
+        Some(d) => Err(d),
+        None => Ok((a.steps, b.steps)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::fuzz::{self, Rng};
+
+    // The rule rewrites something, and the result does the same as before for each input, in no more steps.
+    fn same_behaviour(mem: &[i64]) {
+        let optimised = optimise(mem).expect("refused");
+        assert_ne!(optimised, mem, "nothing rewritten");
+        for input in [0, 1, 5] {
+            let (before, after) = check(mem, &optimised, &[input], 10_000).expect("differs");
+            assert!(after <= before, "{} steps -> {}", before, after);
+        }
+    }
+
+    // Random programs from a fixed seed behave the same before and after, whenever they can be optimised.
+    fn fuzzed(seed: u64) {
+        let mut rng = Rng::new(seed);
+        let mut optimised = 0;
+        for _ in 0..200 {
+            let prog = fuzz::generate(&mut rng, 20);
+            if let Ok(opt) = optimise(&prog.mem) {
+                let result = check(&prog.mem, &opt, &prog.inputs, 10_000);
+                assert!(
+                    result.is_ok(),
+                    "{:?} on {:?}: {:?}",
+                    prog.mem,
+                    prog.inputs,
+                    result
+                );
+                optimised += 1;
+            }
+        }
+        assert!(optimised > 0, "nothing could be optimised");
+    }
+
+    crate::tests! {
+        same_behaviour {
+            fold_add(&[1101, 2, 3, 9, 4, 9, 99, 0, 0, 0]);
+            fold_mul(&[1102, 4, 5, 9, 4, 9, 99, 0, 0, 0]);
+            fold_lt(&[1107, 1, 2, 9, 4, 9, 99, 0, 0, 0]);
+            fold_lt_equal(&[1107, 2, 2, 9, 4, 9, 99, 0, 0, 0]);
+            fold_eq(&[1108, 3, 3, 9, 4, 9, 99, 0, 0, 0]);
+            add_zero_is_move(&[3, 11, 101, 0, 11, 12, 4, 12, 99, 0, 0, 0, 0]);
+            mul_one_is_move(&[3, 11, 1002, 11, 1, 12, 4, 12, 99, 0, 0, 0, 0]);
+            move_to_itself(&[3, 11, 1001, 11, 0, 11, 4, 11, 99, 0, 0, 0]);
+            never_jumps(&[3, 9, 1105, 0, 100, 4, 9, 99, 0, 0]);
+            always_jumps(&[1106, 0, 7, 104, 1, 99, 0, 104, 2, 99]);
+            jumps_to_next(&[3, 9, 1005, 9, 5, 4, 9, 99, 0, 0]);
+            jump_over_relative_base_zero(&[3, 20, 1005, 20, 7, 104, 1, 109, 0, 104, 2, 99]);
+            jump_to_jump(&[1105, 1, 5, 99, 0, 1105, 1, 9, 99, 104, 7, 99]);
+        }
+        fuzzed {
+            fuzz_seed_0(0);
+            fuzz_seed_1(1);
+            fuzz_seed_2(2);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+// Named edits to a program, which check they're editing what they think they are.
+//
+// A patch file has a line naming each patch, followed by its edits.
+// Each edit is an address, the values expected there, ->, and the values to put there instead:
+//
+//     patch free_play
+//     0 1 -> 2
+//
+// Blank lines and lines starting with # are ignored.
+
+use super::symbols::Symbols;
+use super::{Cell, Computer};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Edit<T = i64> {
+    pub addr: usize,
+    pub old: Vec<T>,
+    pub new: Vec<T>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Patch<T = i64> {
+    pub name: String,
+    pub edits: Vec<Edit<T>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Conflict<T> {
+    pub patch: String,
+    pub addr: usize,
+    pub expected: T,
+    pub actual: T,
+}
+
+impl<T: std::fmt::Display> Conflict<T> {
+    // With the address named, if it has a symbol.
+    pub fn render(&self, syms: &Symbols) -> String {
+        format!(
+            "patch {} expected {} at {} but found {}; wrong program?",
+            self.patch,
+            self.expected,
+            syms.addr(self.addr),
+            self.actual
+        )
+    }
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for Conflict<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.render(&Symbols::default()))
+    }
+}
+
+impl<T: Cell> Patch<T> {
+    // Checks every edit before making any, so a conflicting patch changes nothing.
+    pub fn check<F: Fn(usize) -> T>(&self, get: F) -> Result<(), Conflict<T>> {
+        for edit in &self.edits {
+            for (i, &expected) in edit.old.iter().enumerate() {
+                let actual = get(edit.addr + i);
+                if actual != expected {
+                    return Err(Conflict {
+                        patch: self.name.clone(),
+                        addr: edit.addr + i,
+                        expected,
+                        actual,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Can be applied before running or at any point during.
+    pub fn apply(&self, ic: &mut Computer<T>) -> Result<(), Conflict<T>> {
+        self.check(|i| ic.get(i))?;
+        for edit in &self.edits {
+            for (i, &v) in edit.new.iter().enumerate() {
+                ic.set(edit.addr + i, v);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn apply_to_image(&self, mem: &mut Vec<T>) -> Result<(), Conflict<T>> {
+        self.check(|i| mem.get(i).cloned().unwrap_or(T::from(0)))?;
+        for edit in &self.edits {
+            let end = edit.addr + edit.new.len();
+            if mem.len() < end {
+                mem.resize(end, T::from(0));
+            }
+            mem[edit.addr..end].copy_from_slice(&edit.new);
+        }
+        Ok(())
+    }
+}
+
+pub fn parse<T>(s: &str) -> Result<Vec<Patch<T>>, String>
+where
+    T: Cell + std::str::FromStr,
+{
+    let mut patches: Vec<Patch<T>> = Vec::new();
+    for (i, line) in s.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let err = |msg: &str| format!("line {}: {}", i + 1, msg);
+
+        if let Some(name) = line.strip_prefix("patch ") {
+            patches.push(Patch {
+                name: name.trim().to_string(),
+                edits: Vec::new(),
+            });
+            continue;
+        }
+
+        let patch = patches
+            .last_mut()
+            .ok_or_else(|| err("edit before any patch"))?;
+        let (old, new) = line.split_once("->").ok_or_else(|| err("no ->"))?;
+        let values = |s: &str| -> Result<Vec<T>, String> {
+            let vs = s
+                .split_whitespace()
+                .map(|v| v.parse().map_err(|_| err(&format!("bad value {}", v))));
+            vs.collect()
+        };
+        let mut old_words = old.split_whitespace();
+        let addr = old_words.next().ok_or_else(|| err("no address"))?;
+        let addr = addr.parse().map_err(|_| err("bad address"))?;
+        let old = values(&old_words.collect::<Vec<_>>().join(" "))?;
+        let new = values(new)?;
+        if old.len() != new.len() {
+            return Err(err("old and new values differ in number"));
+        }
+        patch.edits.push(Edit { addr, old, new });
+    }
+    Ok(patches)
+}
+
+pub fn load<T>(path: &str) -> Result<Vec<Patch<T>>, String>
+where
+    T: Cell + std::str::FromStr,
+{
+    let s = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    parse(&s).map_err(|e| format!("{}: {}", path, e))
+}
+
+pub fn find<'p, T>(patches: &'p [Patch<T>], name: &str) -> Option<&'p Patch<T>> {
+    patches.iter().find(|p| p.name == name)
+}
@@ -0,0 +1,123 @@
+// Everything worth knowing about where a Computer stopped, for triaging crashes.
+
+use super::disasm::{self, Inst};
+use super::symbols::Symbols;
+use super::{Cell, Computer, Fault, Frame};
+use std::fmt::Write;
+
+// Instructions shown after the current one.
+const AHEAD: usize = 3;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Line<T> {
+    pub pos: usize,
+    pub value: T,
+    // None if the cell isn't a valid instruction.
+    pub inst: Option<Inst>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report<T = i64> {
+    pub fault: Option<Fault>,
+    pub pos: usize,
+    pub relative_base: T,
+    // The instructions executed just before pos, then pos, then what follows it in memory.
+    pub window: Vec<Line<T>>,
+    // Positions executed, oldest first; see Computer::history.
+    pub history: Vec<usize>,
+    pub backtrace: Vec<Frame<T>>,
+    pub input: Vec<T>,
+    pub output: Vec<T>,
+}
+
+impl<T: Cell> Report<T> {
+    pub fn new(ic: &Computer<T>) -> Self {
+        // Values too big for the disassembler are shown raw.
+        let get = |i: usize| ic.get(i).to_i64().unwrap_or(i64::MIN);
+        let line = |pos: usize| Line {
+            pos,
+            value: ic.get(pos),
+            inst: disasm::decode_with(get, pos),
+        };
+
+        let history = ic.history();
+        let mut before: Vec<usize> = history.clone();
+        if before.last() == Some(&ic.pos()) {
+            before.pop();
+        }
+        let skip = before.len().saturating_sub(AHEAD);
+        let mut window: Vec<_> = before[skip..].iter().map(|&p| line(p)).collect();
+
+        let mut pos = ic.pos();
+        for _ in 0..=AHEAD {
+            let l = line(pos);
+            let next = l.inst.as_ref().map(Inst::next);
+            window.push(l);
+            match next {
+                Some(next) => pos = next,
+                None => break,
+            }
+        }
+
+        Self {
+            fault: ic.fault().cloned(),
+            pos: ic.pos(),
+            relative_base: ic.relative_base(),
+            window,
+            history,
+            backtrace: ic.backtrace(),
+            input: ic.pending_input().collect(),
+            output: ic.output.to_vec(),
+        }
+    }
+
+    pub fn render(&self, syms: &Symbols) -> String {
+        let mut out = String::new();
+        match &self.fault {
+            Some(fault) => writeln!(out, "{}", fault).unwrap(),
+            None => writeln!(out, "stopped at {}", syms.addr(self.pos)).unwrap(),
+        }
+
+        let mut prev = None;
+        for line in &self.window {
+            // Executed instructions needn't be adjacent.
+            if prev.is_some_and(|p| p > line.pos || line.pos - p > 4) {
+                writeln!(out, "   ...").unwrap();
+            }
+            let marker = if line.pos == self.pos { "=>" } else { "  " };
+            let inst = match &line.inst {
+                Some(inst) => inst.annotated(syms),
+                None => format!("??? {}", line.value),
+            };
+            writeln!(out, "{} {:>6}  {}", marker, line.pos, inst).unwrap();
+            prev = Some(line.pos);
+        }
+
+        writeln!(out, "relative base {}", self.relative_base).unwrap();
+        let history: Vec<_> = self.history.iter().map(usize::to_string).collect();
+        writeln!(out, "recently executed: {}", history.join(" ")).unwrap();
+        if self.backtrace.is_empty() {
+            writeln!(out, "not in any call").unwrap();
+        }
+        for frame in &self.backtrace {
+            writeln!(out, "  in {}", syms.frame(frame)).unwrap();
+        }
+        let values = |vs: &[T]| -> String {
+            let strs: Vec<_> = vs.iter().map(T::to_string).collect();
+            if strs.is_empty() {
+                "none".to_string()
+            } else {
+                strs.join(",")
+            }
+        };
+        writeln!(out, "pending input: {}", values(&self.input)).unwrap();
+        write!(out, "pending output: {}", values(&self.output)).unwrap();
+        out
+    }
+}
+
+impl<T: Cell> std::fmt::Display for Report<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.render(&Symbols::default()))
+    }
+}
@@ -0,0 +1,126 @@
+// Drawing programs that output (x, y, tile) triples, as day 13's arcade cabinet does.
+// (-1, 0, v) sets the segment display (the score) instead of a tile.
+
+use super::{Cell, Computer};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+// Screens are drawn at most this many tiles across and down, however far apart the tiles are.
+pub const MAX_SIDE: i64 = 1000;
+
+#[derive(Clone, Debug)]
+pub struct Palette {
+    chars: HashMap<i64, char>,
+    // For tiles with no char of their own.
+    pub unknown: char,
+}
+
+impl Default for Palette {
+    // Day 13's: empty, wall, block, paddle, ball.
+    fn default() -> Self {
+        Self::from_chars(" #=_o")
+    }
+}
+
+impl Palette {
+    // The ith char draws tile i.
+    pub fn from_chars(s: &str) -> Self {
+        Self {
+            chars: s.chars().enumerate().map(|(i, c)| (i as i64, c)).collect(),
+            unknown: '?',
+        }
+    }
+
+    pub fn set(&mut self, tile: i64, c: char) {
+        self.chars.insert(tile, c);
+    }
+
+    pub fn get(&self, tile: i64) -> char {
+        self.chars.get(&tile).cloned().unwrap_or(self.unknown)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Screen {
+    pub palette: Palette,
+    tiles: HashMap<(i64, i64), i64>,
+    pub segment: Option<i64>,
+    // The start of a triple whose tile hasn't been output yet.
+    partial: Vec<i64>,
+}
+
+impl Screen {
+    pub fn new(palette: Palette) -> Self {
+        Self {
+            palette,
+            ..Self::default()
+        }
+    }
+
+    pub fn feed<I: IntoIterator<Item = i64>>(&mut self, outputs: I) {
+        for v in outputs {
+            self.partial.push(v);
+            if let [x, y, tile] = self.partial[..] {
+                if (x, y) == (-1, 0) {
+                    self.segment = Some(tile);
+                } else {
+                    self.tiles.insert((x, y), tile);
+                }
+                self.partial.clear();
+            }
+        }
+    }
+
+    // Takes everything the Computer has output so far.
+    pub fn take<T: Cell>(&mut self, ic: &mut Computer<T>) {
+        let outputs = ic
+            .output
+            .drain(..)
+            .map(|v| v.to_i64().expect("tile out of range"));
+        let outputs: Vec<_> = outputs.collect();
+        self.feed(outputs);
+    }
+
+    pub fn tile(&self, x: i64, y: i64) -> Option<i64> {
+        self.tiles.get(&(x, y)).cloned()
+    }
+
+    pub fn count(&self, tile: i64) -> usize {
+        self.tiles.values().filter(|&&t| t == tile).count()
+    }
+
+    // Everything drawn so far, from the top left tile drawn to the bottom right,
+    // but no more than MAX_SIDE tiles each way from the top left, noting how much was left out;
+    // the segment display goes underneath if there is one.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let xs = self.tiles.keys().map(|&(x, _)| x);
+        let ys = self.tiles.keys().map(|&(_, y)| y);
+        if let (Some(min_x), Some(max_x)) = (xs.clone().min(), xs.max()) {
+            let (min_y, max_y) = (ys.clone().min().unwrap(), ys.max().unwrap());
+            let last = |min: i64, max: i64| std::cmp::min(min.saturating_add(MAX_SIDE - 1), max);
+            let (last_x, last_y) = (last(min_x, max_x), last(min_y, max_y));
+            for y in min_y..=last_y {
+                for x in min_x..=last_x {
+                    out.push(self.tile(x, y).map_or(' ', |t| self.palette.get(t)));
+                }
+                out.push('\n');
+            }
+            if (last_x, last_y) != (max_x, max_y) {
+                let side = |min: i64, max: i64| u128::from(max.abs_diff(min)) + 1;
+                let (w, h) = (side(min_x, last_x), side(min_y, last_y));
+                let (all_w, all_h) = (side(min_x, max_x), side(min_y, max_y));
+                writeln!(
+                    out,
+                    "(only the top left {}x{} of {}x{})",
+                    w, h, all_w, all_h
+                )
+                .unwrap();
+            }
+        }
+        if let Some(v) = self.segment {
+            writeln!(out, "{}", v).unwrap();
+        }
+        out
+    }
+}
@@ -0,0 +1,169 @@
+// Recording a Computer's inputs and outputs, and replaying them to check nothing changed.
+//
+// A session file has one line per run of consecutive inputs or of consecutive outputs:
+//
+//     in 1
+//     out 0 0 0 7
+//
+// Blank lines and lines starting with # are ignored.
+
+use super::symbols::Symbols;
+pub use super::Event;
+use super::{Cell, Computer};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Session<T = i64> {
+    pub events: Vec<Event<T>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch<T> {
+    // Index into the session's events.
+    pub index: usize,
+    // The instruction that produced the event, or where the Computer stopped.
+    pub pos: usize,
+    // None if the session ended here.
+    pub expected: Option<Event<T>>,
+    // None if the Computer halted, or wanted input when the session had an output.
+    pub actual: Option<Event<T>>,
+}
+
+impl<T: std::fmt::Debug> Mismatch<T> {
+    // With the position named, if it has a symbol.
+    pub fn render(&self, syms: &Symbols) -> String {
+        format!(
+            "event {} at {}: expected {:?}, got {:?}",
+            self.index,
+            syms.addr(self.pos),
+            self.expected,
+            self.actual
+        )
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Display for Mismatch<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.render(&Symbols::default()))
+    }
+}
+
+impl<T: Cell> Session<T> {
+    // Everything the Computer has read and written since Computer::record.
+    pub fn from_journal(ic: &Computer<T>) -> Self {
+        Self {
+            events: ic.journal().to_vec(),
+        }
+    }
+
+    pub fn inputs(&self) -> impl Iterator<Item = T> + '_ {
+        self.events.iter().filter_map(|e| match e {
+            Event::In(v) => Some(*v),
+            Event::Out(_) => None,
+        })
+    }
+}
+
+impl<T> Session<T>
+where
+    T: Cell + std::str::FromStr,
+{
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut events = Vec::new();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut words = line.split_whitespace();
+            let event: fn(T) -> Event<T> = match words.next() {
+                Some("in") => Event::In,
+                Some("out") => Event::Out,
+                Some(w) => return Err(format!("line {}: expected in or out, not {}", i + 1, w)),
+                None => unreachable!("empty lines already skipped"),
+            };
+            for w in words {
+                let v = w
+                    .parse()
+                    .map_err(|_| format!("line {}: bad value {}", i + 1, w))?;
+                events.push(event(v));
+            }
+        }
+        Ok(Self { events })
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let s = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Self::parse(&s).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+impl<T: Cell> std::fmt::Display for Session<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut prev_in = None;
+        for e in &self.events {
+            let (is_in, v) = match e {
+                Event::In(v) => (true, v),
+                Event::Out(v) => (false, v),
+            };
+            if prev_in == Some(is_in) {
+                write!(f, " {}", v)?;
+            } else {
+                if prev_in.is_some() {
+                    writeln!(f)?;
+                }
+                write!(f, "{} {}", if is_in { "in" } else { "out" }, v)?;
+            }
+            prev_in = Some(is_in);
+        }
+        if prev_in.is_some() {
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// Feeds the session's inputs to the Computer as it asks for them,
+// checking every input and output against the session in order.
+// Stops at the first difference, or once the session is used up and the Computer halts or blocks.
+pub fn replay<T: Cell>(ic: &mut Computer<T>, session: &Session<T>) -> Result<(), Mismatch<T>> {
+    ic.record();
+    let base = ic.journal().len();
+    let mut checked = 0;
+
+    loop {
+        while base + checked < ic.journal().len() {
+            let actual = ic.journal()[base + checked];
+            let expected = session.events.get(checked).cloned();
+            if expected != Some(actual) {
+                return Err(Mismatch {
+                    index: checked,
+                    pos: ic.last_access().pos,
+                    expected,
+                    actual: Some(actual),
+                });
+            }
+            checked += 1;
+        }
+
+        if ic.is_halted() {
+            break;
+        }
+        if ic.is_blocked() {
+            match session.events.get(checked) {
+                Some(&Event::In(v)) => ic.push_input(v),
+                _ => break,
+            }
+        }
+        ic.step();
+    }
+
+    match session.events.get(checked) {
+        Some(&expected) => Err(Mismatch {
+            index: checked,
+            pos: ic.pos(),
+            expected: Some(expected),
+            actual: None,
+        }),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,101 @@
+// Routines that compiled programs get without defining them; see compile::link.
+// They're source in the compiler's own language: compiled programs link them in before compiling,
+// and any other program can have them compiled to relocatable intcode (routines) and appended (link).
+//
+// div, mod: like Rust's / and %, by doubling and subtracting; dividing by zero faults.
+// print_int: outputs a number as ASCII digits. read_int: reads an ASCII number up to a newline.
+// memcpy, memset: dst first, then src or the value, then the count.
+// alloc, free: a first-fit heap far above the stack.
+//
+// Calling one from intcode follows compile's calling convention: with rb past everything the caller uses,
+// store the return address at [rb] and the arguments at [rb+1].., then jump to the entry;
+// the result is at [rb+1] afterwards. The routines use the memory above rb for their frames.
+
+use super::compile::{self, Program, Relocatable};
+
+pub const SOURCE: &str = include_str!("stdlib.icl");
+
+pub fn program() -> Program {
+    compile::parse(SOURCE).unwrap_or_else(|e| panic!("stdlib.icl: {}", e))
+}
+
+// The named routines and the helpers they call, compiled together.
+pub fn routines(names: &[&str]) -> Result<Relocatable, String> {
+    let lib = program();
+    let mut prog = Program::default();
+    for &name in names {
+        let f = lib.funcs.iter().find(|f| f.name == name);
+        let f = f.ok_or_else(|| format!("no routine {}", name))?;
+        if !prog.funcs.contains(f) {
+            prog.funcs.push(f.clone());
+        }
+    }
+    compile::link(&mut prog, &lib);
+    compile::compile_functions(&prog)
+}
+
+// Appends the routines to the image, and returns where each one starts.
+// The image mustn't use the memory past its end, as a compiled program does for its stack.
+pub fn link(image: &mut Vec<i64>, routines: &Relocatable) -> Vec<(String, usize)> {
+    let base = image.len();
+    image.extend(routines.at(base));
+    let entries = routines.entries.iter();
+    entries
+        .map(|(name, at)| (name.clone(), base + at))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::Computer;
+
+    // Links the routine onto a program that sets rb to 1000, calls it on args, outputs the result, and halts,
+    // padded with zeros to pad cells first so the routines go elsewhere.
+    fn calls(name: &str, args: &[i64], input: &str, pad: usize, want: &[i64]) {
+        let mut image = vec![109, 1000];
+        for (i, &a) in args.iter().enumerate() {
+            image.extend([21101, a, 0, i as i64 + 1]);
+        }
+        let ret = image.len() as i64 + 4 + 3;
+        image.extend([21101, ret, 0, 0, 1105, 1, -1, 204, 1, 99]);
+        let jump = image.len() - 4;
+        image.resize(std::cmp::max(image.len(), pad), 0);
+
+        let routines = routines(&[name]).unwrap();
+        let base = image.len();
+        let entries = link(&mut image, &routines);
+        assert_eq!(image[base..], routines.at(base));
+        let &(_, entry) = entries.iter().find(|(n, _)| n == name).unwrap();
+        image[jump] = entry as i64;
+
+        let mut ic = Computer::new(&image);
+        for b in input.bytes() {
+            ic.push_input(i64::from(b));
+        }
+        ic.cont();
+        assert!(ic.is_halted(), "didn't halt");
+        assert_eq!(ic.output.drain(..).collect::<Vec<_>>(), want);
+    }
+
+    fn unknown(name: &str) {
+        assert_eq!(routines(&[name]), Err(format!("no routine {}", name)));
+    }
+
+    crate::tests! {
+        calls {
+            div("div", &[-17, 5], "", 0, &[-3]);
+            modulo("mod", &[-17, 5], "", 0, &[-2]);
+            abs("abs", &[-4], "", 0, &[4]);
+            print_int("print_int", &[-120], "", 0, &[45, 49, 50, 48, 0]);
+            read_int("read_int", &[], "-42\n", 0, &[-42]);
+            alloc("alloc", &[3], "", 0, &[1048579]);
+            relocated_div("div", &[100, 7], "", 500, &[14]);
+            relocated_mod("mod", &[100, 7], "", 500, &[2]);
+            relocated_print_int("print_int", &[37], "", 500, &[51, 55, 0]);
+        }
+        unknown {
+            unknown_routine("sqrt");
+        }
+    }
+}
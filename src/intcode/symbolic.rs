@@ -0,0 +1,459 @@
+// Running code on unknowns, to find out what it computes rather than recognising how it's written.
+//
+// Memory holds polynomials in variables x0, x1, ...: function arguments first, then inputs as they're read.
+// Anything that needs a concrete value (an address, a jump, a comparison) and doesn't have one stops the run,
+// and says what it would have needed to know; a comparison's difference is often the interesting part.
+
+use super::arity;
+use std::collections::{BTreeMap, HashMap};
+
+// Each variable in ascending order, with its power: x0^2 x1 is [(0, 2), (1, 1)].
+type Monomial = Vec<(usize, u32)>;
+
+// Beyond these, a product is too big to be worth carrying on with, and Poly::mul gives up.
+pub const MAX_DEGREE: usize = 64;
+pub const MAX_TERMS: usize = 1000;
+
+// Coefficients are wrapping, as the puzzles never get near overflow.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Poly {
+    // No zero coefficients.
+    terms: BTreeMap<Monomial, i64>,
+}
+
+fn degree(m: &Monomial) -> usize {
+    m.iter().map(|&(_, p)| p as usize).sum()
+}
+
+// Both monomials' variables, adding the powers of any they share.
+fn product(m1: &Monomial, m2: &Monomial) -> Monomial {
+    let mut m = m1.clone();
+    for &(v, p) in m2 {
+        match m.binary_search_by_key(&v, |&(v, _)| v) {
+            Ok(i) => m[i].1 += p,
+            Err(i) => m.insert(i, (v, p)),
+        }
+    }
+    m
+}
+
+impl Poly {
+    pub fn constant(c: i64) -> Self {
+        let mut p = Self::default();
+        if c != 0 {
+            p.terms.insert(Vec::new(), c);
+        }
+        p
+    }
+
+    pub fn var(i: usize) -> Self {
+        let mut p = Self::default();
+        p.terms.insert(vec![(i, 1)], 1);
+        p
+    }
+
+    pub fn as_const(&self) -> Option<i64> {
+        match self.terms.len() {
+            0 => Some(0),
+            1 => self.terms.get(&Vec::new()).cloned(),
+            _ => None,
+        }
+    }
+
+    // The coefficient of the product of vars, which may repeat for powers.
+    pub fn coeff(&self, vars: &[usize]) -> i64 {
+        let m = vars
+            .iter()
+            .fold(Vec::new(), |m, &v| product(&m, &vec![(v, 1)]));
+        self.terms.get(&m).cloned().unwrap_or(0)
+    }
+
+    pub fn degree(&self) -> usize {
+        self.terms.keys().map(degree).max().unwrap_or(0)
+    }
+
+    pub fn eval(&self, vars: &[i64]) -> i64 {
+        let term = |(m, &c): (&Monomial, &i64)| {
+            m.iter()
+                .fold(c, |acc, &(v, p)| acc.wrapping_mul(vars[v].wrapping_pow(p)))
+        };
+        self.terms.iter().map(term).fold(0, i64::wrapping_add)
+    }
+
+    // Coefficients of x0..x(n-1) and the constant, if no term has degree over 1.
+    pub fn affine(&self, n: usize) -> Option<(Vec<i64>, i64)> {
+        if self.degree() > 1 || self.terms.keys().flatten().any(|&(v, _)| v >= n) {
+            return None;
+        }
+        let coeffs = (0..n).map(|v| self.coeff(&[v])).collect();
+        Some((coeffs, self.coeff(&[])))
+    }
+
+    fn insert(&mut self, m: Monomial, c: i64) {
+        let sum = self.terms.get(&m).cloned().unwrap_or(0).wrapping_add(c);
+        if sum == 0 {
+            self.terms.remove(&m);
+        } else {
+            self.terms.insert(m, sum);
+        }
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        let mut p = self.clone();
+        for (m, &c) in &other.terms {
+            p.insert(m.clone(), c);
+        }
+        p
+    }
+
+    // None if the product would have a degree over MAX_DEGREE or more than MAX_TERMS terms.
+    pub fn mul(&self, other: &Self) -> Option<Self> {
+        if self.degree() + other.degree() > MAX_DEGREE {
+            return None;
+        }
+        let mut p = Self::default();
+        for (m1, &c1) in &self.terms {
+            for (m2, &c2) in &other.terms {
+                p.insert(product(m1, m2), c1.wrapping_mul(c2));
+                if p.terms.len() > MAX_TERMS {
+                    return None;
+                }
+            }
+        }
+        Some(p)
+    }
+
+    pub fn sub(&self, other: &Self) -> Self {
+        let mut p = self.clone();
+        for (m, &c) in &other.terms {
+            p.insert(m.clone(), c.wrapping_neg());
+        }
+        p
+    }
+}
+
+impl std::fmt::Display for Poly {
+    // Highest degree first, like 3*x0^2*x1 - 2*x1 + 7.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.terms.is_empty() {
+            return write!(f, "0");
+        }
+        let mut terms: Vec<_> = self.terms.iter().collect();
+        terms.sort_by_key(|(m, _)| std::cmp::Reverse(degree(m)));
+        for (i, (m, &c)) in terms.into_iter().enumerate() {
+            let sign = if c < 0 { "-" } else { "+" };
+            match i {
+                0 if c < 0 => write!(f, "-")?,
+                0 => {}
+                _ => write!(f, " {} ", sign)?,
+            }
+            let mut parts = Vec::new();
+            if c.unsigned_abs() != 1 || m.is_empty() {
+                parts.push(c.unsigned_abs().to_string());
+            }
+            for &(v, power) in m {
+                if power == 1 {
+                    parts.push(format!("x{}", v));
+                } else {
+                    parts.push(format!("x{}^{}", v, power));
+                }
+            }
+            write!(f, "{}", parts.join("*"))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compare {
+    Lt,
+    Eq,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Reason {
+    // lt or eq on a - b that isn't constant.
+    Compare(Compare, Poly),
+    // A conditional jump on a value that isn't constant.
+    Branch(Poly),
+    // An address, jump target, relative base adjustment, or the instruction itself isn't constant.
+    NotConstant(Poly),
+    BadInstruction(i64),
+    // Reading from or jumping to a negative address.
+    BadTarget(i64),
+    // An address or relative base that doesn't fit in an i64.
+    Overflow,
+    // A product past MAX_DEGREE or MAX_TERMS.
+    TooBig,
+    OutOfSteps,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stuck {
+    pub pos: usize,
+    pub reason: Reason,
+}
+
+impl std::fmt::Display for Stuck {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "stuck at {}: ", self.pos)?;
+        match &self.reason {
+            Reason::Compare(Compare::Lt, diff) => write!(f, "depends on whether {} < 0", diff),
+            Reason::Compare(Compare::Eq, diff) => write!(f, "depends on whether {} == 0", diff),
+            Reason::Branch(cond) => write!(f, "jump depends on {}", cond),
+            Reason::NotConstant(p) => write!(f, "needs a constant, not {}", p),
+            Reason::BadInstruction(op) => write!(f, "invalid instruction {}", op),
+            Reason::BadTarget(a) => write!(f, "invalid address or jump target {}", a),
+            Reason::Overflow => write!(f, "address arithmetic overflow"),
+            Reason::TooBig => write!(
+                f,
+                "product over degree {} or {} terms",
+                MAX_DEGREE, MAX_TERMS
+            ),
+            Reason::OutOfSteps => write!(f, "out of steps"),
+        }
+    }
+}
+
+// Where Machine::call sets up the frame, and returns to; far from any program's own memory,
+// and small enough for a 32-bit usize.
+const STACK: usize = 1 << 24;
+const RETURN: usize = 1 << 30;
+
+pub struct Machine<'a> {
+    image: &'a [i64],
+    mem: HashMap<usize, Poly>,
+    pos: usize,
+    relative_base: i64,
+    halted: bool,
+    next_var: usize,
+    pub output: Vec<Poly>,
+    pub budget: u64,
+}
+
+impl<'a> Machine<'a> {
+    pub fn new(image: &'a [i64]) -> Self {
+        Self {
+            image,
+            mem: HashMap::new(),
+            pos: 0,
+            relative_base: 0,
+            halted: false,
+            next_var: 0,
+            output: Vec::new(),
+            budget: 1_000_000,
+        }
+    }
+
+    pub fn get(&self, i: usize) -> Poly {
+        match self.mem.get(&i) {
+            Some(p) => p.clone(),
+            None => Poly::constant(self.image.get(i).cloned().unwrap_or(0)),
+        }
+    }
+
+    pub fn set(&mut self, i: usize, p: Poly) {
+        self.mem.insert(i, p);
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    // A fresh variable, numbered after any already handed out.
+    pub fn unknown(&mut self) -> Poly {
+        self.next_var += 1;
+        Poly::var(self.next_var - 1)
+    }
+
+    // Calls the function at addr with n unknown arguments (x0.. if nothing else has been numbered yet),
+    // and returns its result, which it leaves where the calling convention says to.
+    pub fn call(&mut self, addr: usize, n: usize) -> Result<Poly, Stuck> {
+        self.set(STACK, Poly::constant(RETURN as i64));
+        for i in 0..n {
+            let arg = self.unknown();
+            self.set(STACK + 1 + i, arg);
+        }
+        self.pos = addr;
+        self.relative_base = STACK as i64;
+        self.run()?;
+        Ok(self.get(STACK + 1))
+    }
+
+    // Runs until halting or returning from call; inputs are fresh unknowns.
+    pub fn run(&mut self) -> Result<(), Stuck> {
+        for _ in 0..self.budget {
+            if self.halted || self.pos == RETURN {
+                return Ok(());
+            }
+            self.step()?;
+        }
+        Err(self.stuck(Reason::OutOfSteps))
+    }
+
+    fn stuck(&self, reason: Reason) -> Stuck {
+        Stuck {
+            pos: self.pos,
+            reason,
+        }
+    }
+
+    fn constant(&self, p: &Poly) -> Result<i64, Stuck> {
+        p.as_const()
+            .ok_or_else(|| self.stuck(Reason::NotConstant(p.clone())))
+    }
+
+    fn addr(&self, a: i64) -> Result<usize, Stuck> {
+        usize::try_from(a).map_err(|_| self.stuck(Reason::BadTarget(a)))
+    }
+
+    // The address a param refers to, for modes other than immediate.
+    fn param_addr(&self, i: usize, mode: i64) -> Result<usize, Stuck> {
+        let v = self.constant(&self.get(self.pos + i))?;
+        let base = if mode == 2 { self.relative_base } else { 0 };
+        let a = v
+            .checked_add(base)
+            .ok_or_else(|| self.stuck(Reason::Overflow))?;
+        self.addr(a)
+    }
+
+    fn read(&self, i: usize, mode: i64) -> Result<Poly, Stuck> {
+        match mode {
+            1 => Ok(self.get(self.pos + i)),
+            _ => Ok(self.get(self.param_addr(i, mode)?)),
+        }
+    }
+
+    fn step(&mut self) -> Result<(), Stuck> {
+        let opcode = self.constant(&self.get(self.pos))?;
+        let (num_inputs, has_output) =
+            arity(opcode).ok_or_else(|| self.stuck(Reason::BadInstruction(opcode)))?;
+        let width = 1 + usize::from(num_inputs) + usize::from(has_output);
+        let modes = [
+            (opcode / 100) % 10,
+            (opcode / 1000) % 10,
+            (opcode / 10000) % 10,
+        ];
+        let valid = modes[..width - 1].iter().all(|&m| m <= 2)
+            && opcode < [100, 1_000, 10_000, 100_000][width - 1]
+            && !(has_output && modes[width - 2] == 1);
+        if !valid {
+            return Err(self.stuck(Reason::BadInstruction(opcode)));
+        }
+
+        let a = if num_inputs >= 1 {
+            self.read(1, modes[0])?
+        } else {
+            Poly::default()
+        };
+        let b = if num_inputs >= 2 {
+            self.read(2, modes[1])?
+        } else {
+            Poly::default()
+        };
+        let out = if has_output {
+            Some(self.param_addr(width - 1, modes[width - 2])?)
+        } else {
+            None
+        };
+
+        let mut next = self.pos + width;
+        let written = match opcode % 100 {
+            1 => Some(a.add(&b)),
+            2 => Some(a.mul(&b).ok_or_else(|| self.stuck(Reason::TooBig))?),
+            3 => Some(self.unknown()),
+            4 => {
+                self.output.push(a);
+                None
+            }
+            5 | 6 => {
+                let cond = a
+                    .as_const()
+                    .ok_or_else(|| self.stuck(Reason::Branch(a.clone())))?;
+                if (cond != 0) == (opcode % 100 == 5) {
+                    next = self.addr(self.constant(&b)?)?;
+                }
+                None
+            }
+            7 | 8 => {
+                let diff = a.sub(&b);
+                let lt = opcode % 100 == 7;
+                let result = match diff.as_const() {
+                    Some(d) if lt => d < 0,
+                    Some(d) => d == 0,
+                    None => {
+                        let cmp = if lt { Compare::Lt } else { Compare::Eq };
+                        return Err(self.stuck(Reason::Compare(cmp, diff)));
+                    }
+                };
+                Some(Poly::constant(i64::from(result)))
+            }
+            9 => {
+                let rb = self.relative_base.checked_add(self.constant(&a)?);
+                self.relative_base = rb.ok_or_else(|| self.stuck(Reason::Overflow))?;
+                None
+            }
+            _ => {
+                self.halted = true;
+                return Ok(());
+            }
+        };
+
+        if let (Some(addr), Some(v)) = (out, written) {
+            self.set(addr, v);
+        }
+        self.pos = next;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn power(n: u32) {
+        let mut p = Poly::var(0);
+        for _ in 1..n {
+            p = p.mul(&Poly::var(0)).unwrap();
+        }
+        let vars = vec![0; n as usize];
+        assert_eq!(p.coeff(&vars), 1);
+        assert_eq!(p.degree(), n as usize);
+        assert_eq!(p.eval(&[2]), 2_i64.wrapping_pow(n));
+    }
+
+    fn too_big(image: &[i64], pos: usize) {
+        let mut m = Machine::new(image);
+        let stuck = m.run().unwrap_err();
+        assert_eq!(
+            stuck,
+            Stuck {
+                pos,
+                reason: Reason::TooBig
+            }
+        );
+    }
+
+    crate::tests! {
+        power {
+            power_one(1);
+            power_square(2);
+            power_max(MAX_DEGREE as u32);
+        }
+        too_big {
+            // x = input; 40 times, x = x * x.
+            too_big_squaring(&[
+                109, 44, 3, 43, 1001, 43, 0, 41, 1101, 0, 0, 42, 1007, 42, 40, 43, 1006, 43, 38, 2, 41, 41, 43,
+                1001, 43, 0, 41, 1001, 42, 1, 43, 1001, 43, 0, 42, 1105, 1, 12, 4, 41, 99, 0, 0, 0,
+            ], 19);
+            // Squaring x0 + ... + x10 twice gives 1001 terms.
+            too_big_terms(&[
+                3, 100, 3, 101, 1, 100, 101, 100, 3, 101, 1, 100, 101, 100, 3, 101, 1, 100, 101, 100,
+                3, 101, 1, 100, 101, 100, 3, 101, 1, 100, 101, 100, 3, 101, 1, 100, 101, 100,
+                3, 101, 1, 100, 101, 100, 3, 101, 1, 100, 101, 100, 3, 101, 1, 100, 101, 100,
+                3, 101, 1, 100, 101, 100,
+                2, 100, 100, 100, 2, 100, 100, 100, 2, 100, 100, 100, 99,
+            ], 66);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+// Names and comments for addresses, kept in a sidecar file next to a program.
+//
+// One symbol per line: an address or a half-open range, a name, then an optional comment.
+//
+//     # day 23
+//     67 rx_slots number of receive slots
+//     1901..1934 weight_bits
+//
+// Blank lines and lines starting with # are ignored.
+
+use super::Frame;
+use std::ops::Range;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    pub range: Range<usize>,
+    pub name: String,
+    pub comment: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Symbols {
+    // Sorted by start address.
+    syms: Vec<Symbol>,
+}
+
+fn parse_range(s: &str) -> Option<Range<usize>> {
+    match s.split_once("..") {
+        Some((start, end)) => {
+            let range = start.parse().ok()?..end.parse().ok()?;
+            Some(range).filter(|r| !r.is_empty())
+        }
+        None => s.parse().ok().map(|a: usize| a..(a + 1)),
+    }
+}
+
+impl Symbols {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut syms = Vec::new();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            // However much whitespace separates them; the comment is the rest of the words.
+            let mut words = line.split_whitespace();
+            let addr = words.next().unwrap_or("");
+            let range = parse_range(addr).ok_or(format!("line {}: bad address {}", i + 1, addr))?;
+            let name = words.next().ok_or(format!("line {}: no name", i + 1))?;
+            syms.push(Symbol {
+                range,
+                name: name.to_string(),
+                comment: words.collect::<Vec<_>>().join(" "),
+            });
+        }
+        syms.sort_by_key(|s| s.range.start);
+        Ok(Self { syms })
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let s = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Self::parse(&s).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    // Symbols for a program come from $INTCODE_SYMBOLS if set, else <program>.sym if it exists.
+    // No symbols at all is fine, but a file that exists and doesn't parse is not.
+    pub fn for_program(program_path: Option<&str>) -> Self {
+        let path = std::env::var("INTCODE_SYMBOLS")
+            .ok()
+            .or_else(|| program_path.map(|p| format!("{}.sym", p)));
+        match path {
+            Some(path) if std::path::Path::new(&path).exists() => {
+                Self::load(&path).unwrap_or_else(|e| panic!("{}", e))
+            }
+            _ => Self::default(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.syms.is_empty()
+    }
+
+    // Symbols that start exactly here, for labelling listings.
+    pub fn starting_at(&self, addr: usize) -> impl Iterator<Item = &Symbol> {
+        let first = self.syms.partition_point(|s| s.range.start < addr);
+        let syms = self.syms[first..].iter();
+        syms.take_while(move |s| s.range.start == addr)
+    }
+
+    // The narrowest symbol containing addr.
+    pub fn lookup(&self, addr: usize) -> Option<&Symbol> {
+        let before = self.syms.partition_point(|s| s.range.start <= addr);
+        let containing = self.syms[..before]
+            .iter()
+            .filter(|s| s.range.contains(&addr));
+        containing.min_by_key(|s| s.range.len())
+    }
+
+    // name, or name+offset if inside a range.
+    pub fn describe(&self, addr: usize) -> Option<String> {
+        self.lookup(addr).map(|s| {
+            let offset = addr - s.range.start;
+            if offset == 0 {
+                s.name.clone()
+            } else {
+                format!("{}+{}", s.name, offset)
+            }
+        })
+    }
+
+    // The address itself, followed by its name if it has one.
+    pub fn addr(&self, addr: usize) -> String {
+        match self.describe(addr) {
+            Some(name) => format!("{} <{}>", addr, name),
+            None => addr.to_string(),
+        }
+    }
+
+    // One line of a backtrace: the function and its arguments, then where it returns to.
+    pub fn frame<T: std::fmt::Display>(&self, frame: &Frame<T>) -> String {
+        let args: Vec<_> = frame.args.iter().map(T::to_string).collect();
+        format!(
+            "{}({}) returning to {}",
+            self.addr(frame.function),
+            args.join(", "),
+            self.addr(frame.ret)
+        )
+    }
+}
@@ -0,0 +1,136 @@
+// Watchpoints: noticing when a Computer reads or writes particular addresses.
+
+use super::{Cell, Computer};
+use std::ops::Range;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum On {
+    Read,
+    Write,
+    Both,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    // Record the hit and keep going.
+    Log,
+    // Record the hit and stop before the next instruction.
+    Stop,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub range: Range<usize>,
+    pub on: On,
+    pub action: Action,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access<T> {
+    Read(T),
+    Write { old: T, new: T },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hit<T> {
+    // Index of the watchpoint, in the order added.
+    pub watch: usize,
+    // Steps the Watcher has seen, counting this one.
+    pub step: u64,
+    // The instruction responsible.
+    pub pos: usize,
+    pub addr: usize,
+    pub access: Access<T>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Watcher<T = i64> {
+    watches: Vec<Watchpoint>,
+    pub log: Vec<Hit<T>>,
+    steps: u64,
+}
+
+impl<T> Default for Watcher<T> {
+    fn default() -> Self {
+        Self {
+            watches: Vec::new(),
+            log: Vec::new(),
+            steps: 0,
+        }
+    }
+}
+
+impl<T: Cell> Watcher<T> {
+    pub fn add(&mut self, range: Range<usize>, on: On, action: Action) -> usize {
+        self.watches.push(Watchpoint { range, on, action });
+        self.watches.len() - 1
+    }
+
+    // Call after each step; logs hits, and returns the first that should stop execution.
+    pub fn check(&mut self, ic: &Computer<T>) -> Option<Hit<T>> {
+        if ic.is_blocked() || ic.fault().is_some() {
+            return None;
+        }
+        self.steps += 1;
+        let access = ic.last_access();
+
+        let reads = access.reads.iter().flatten();
+        let reads = reads.map(|&(a, v)| (a, Access::Read(v)));
+        let write = access.write.map(|(a, old)| {
+            let new = ic.get(a);
+            (a, Access::Write { old, new })
+        });
+
+        let mut stop = None;
+        for (addr, acc) in reads.chain(write) {
+            let is_write = matches!(acc, Access::Write { .. });
+            for (watch, w) in self.watches.iter().enumerate() {
+                let wanted = match w.on {
+                    On::Read => !is_write,
+                    On::Write => is_write,
+                    On::Both => true,
+                };
+                if !wanted || !w.range.contains(&addr) {
+                    continue;
+                }
+                let hit = Hit {
+                    watch,
+                    step: self.steps,
+                    pos: access.pos,
+                    addr,
+                    access: acc,
+                };
+                self.log.push(hit);
+                if w.action == Action::Stop && stop.is_none() {
+                    stop = Some(hit);
+                }
+            }
+        }
+        stop
+    }
+
+    // Like Computer::cont, but also stops at a Stop watchpoint, returning what it hit.
+    pub fn cont(&mut self, ic: &mut Computer<T>) -> Option<Hit<T>> {
+        while !ic.is_halted() && !ic.is_blocked() {
+            ic.step();
+            if let Some(hit) = self.check(ic) {
+                return Some(hit);
+            }
+        }
+        None
+    }
+
+    pub fn cont_in(&mut self, ic: &mut Computer<T>, input: T) -> Option<Hit<T>> {
+        ic.push_input(input);
+        self.cont(ic)
+    }
+
+    // Every logged write to addr, oldest first.
+    pub fn history(&self, addr: usize) -> impl Iterator<Item = &Hit<T>> {
+        let is_write = |h: &&Hit<T>| matches!(h.access, Access::Write { .. });
+        self.log
+            .iter()
+            .filter(is_write)
+            .filter(move |h| h.addr == addr)
+    }
+}
@@ -1,7 +1,17 @@
+// Without the std feature, only the intcode core is built, for targets with just alloc.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 pub mod intcode;
+#[cfg(feature = "std")]
 pub mod search;
 
+#[cfg(feature = "std")]
+use intcode::format::Format;
+#[cfg(feature = "std")]
 use std::env;
+#[cfg(feature = "std")]
 use std::fs;
 
 #[macro_export]
@@ -22,12 +32,13 @@ macro_rules! tests {
     }
 }
 
+#[cfg(feature = "std")]
 pub fn numbers<T>(s: &str) -> Vec<T>
 where
     T: std::str::FromStr,
     <T as std::str::FromStr>::Err: std::fmt::Debug,
 {
-    s.split(|c: char| c != '-' && !c.is_digit(10))
+    s.split(|c: char| c != '-' && !c.is_ascii_digit())
         .filter_map(|d| {
             if d.is_empty() {
                 None
@@ -38,24 +49,70 @@ where
         .collect()
 }
 
+// A program given as a file in any of intcode::format's formats,
+// or in ARGV as a single argument separated by commas.
+#[cfg(feature = "std")]
 pub fn read_input_file_or_intcode() -> Vec<i64> {
-    let filename = env::args()
+    let arg = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "/dev/stdin".to_string());
+
+    if arg.contains(',') {
+        intcode::format::parse_text(arg.trim(), Format::Csv).unwrap_or_else(|e| panic!("{}", e))
+    } else {
+        intcode::format::load(&arg).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+// For days that can also take something other than intcode, like day 17's map:
+// the intcode if the input is intcode in any format, otherwise the input as text.
+#[cfg(feature = "std")]
+pub fn read_input_intcode_or_text() -> Result<Vec<i64>, String> {
+    let arg = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "/dev/stdin".to_string());
+
+    if arg.contains(',') {
+        return Ok(read_input_file_or_intcode());
+    }
+    // Read only once, as stdin can't be read twice.
+    let bytes = fs::read(&arg).expect("couldn't read file");
+    intcode::format::parse(&bytes, Format::detect(&bytes))
+        .map_err(|_| String::from_utf8_lossy(&bytes).into_owned())
+}
+
+// For wider cells, e.g. Vec<i128> for intcode::Computer<i128>.
+// Only the text formats, as binary is 8 bytes a value.
+#[cfg(feature = "std")]
+pub fn read_input_file_or_intcode_as<T>() -> Vec<T>
+where
+    T: std::str::FromStr,
+{
+    let arg = env::args()
         .nth(1)
         .unwrap_or_else(|| "/dev/stdin".to_string());
 
-    let comma_separated = if filename.contains(',') {
-        filename
+    let mem = if arg.contains(',') {
+        intcode::format::parse_text(arg.trim(), Format::Csv)
     } else {
-        fs::read_to_string(filename).expect("couldn't read file")
+        let s = fs::read_to_string(&arg).expect("couldn't read file");
+        intcode::format::parse_text(&s, Format::Text)
     };
+    mem.unwrap_or_else(|e| panic!("{}: {}", arg, e))
+}
 
-    comma_separated
-        .trim()
-        .split(',')
-        .map(|i| i.parse().expect("can't parse integer"))
-        .collect()
+// Inputs given to intcode tools on the command line:
+// comma-separated numbers, or otherwise ASCII text (with \n for newlines).
+#[cfg(feature = "std")]
+pub fn intcode_inputs(arg: &str) -> Vec<i64> {
+    let nums: Result<Vec<i64>, _> = arg.split(',').map(str::parse).collect();
+    match nums {
+        Ok(nums) => nums,
+        Err(_) => arg.replace("\\n", "\n").bytes().map(i64::from).collect(),
+    }
 }
 
+#[cfg(feature = "std")]
 pub fn read_input_lines<T, F>(f: F) -> Vec<T>
 where
     F: FnMut(&str) -> T,
@@ -63,6 +120,7 @@ where
     read_input_file().lines().map(f).collect()
 }
 
+#[cfg(feature = "std")]
 pub fn read_input_file() -> String {
     let filename = env::args()
         .nth(1)
@@ -130,7 +130,7 @@ where
             let tentative_g_score = g_score[&current] + ndist;
             if g_score
                 .get(&neighbour)
-                .map_or(false, |&g| tentative_g_score >= g)
+                .is_some_and(|&g| tentative_g_score >= g)
             {
                 continue;
             }